@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use crate::interpreters::disassemble;
+
+/**
+ * An operand as written, before labels are resolved.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Operand<'a> {
+    Register(u16),
+    I,
+    // [I], the memory I points to
+    AtI,
+    DelayTimer,
+    SoundTimer,
+    Key,
+    Font,
+    Bcd,
+    // a number or a label
+    Value(&'a str),
+}
+
+impl Operand<'_> {
+    fn parse(text: &str) -> Operand<'_> {
+        let register = text
+            .strip_prefix(['V', 'v'])
+            .filter(|digit| digit.len() == 1)
+            .and_then(|digit| u16::from_str_radix(digit, 16).ok());
+        if let Some(x) = register {
+            return Operand::Register(x);
+        }
+        match text.to_ascii_uppercase().as_str() {
+            "I" => Operand::I,
+            "[I]" => Operand::AtI,
+            "DT" => Operand::DelayTimer,
+            "ST" => Operand::SoundTimer,
+            "K" => Operand::Key,
+            "F" => Operand::Font,
+            "B" => Operand::Bcd,
+            _ => Operand::Value(text),
+        }
+    }
+}
+
+/**
+ * Parses a number in decimal, hex (`0x1f`) or binary (`0b0110`).
+ */
+fn parse_number(text: &str) -> Option<u16> {
+    if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        u16::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = text.strip_prefix("0b").or(text.strip_prefix("0B")) {
+        u16::from_str_radix(binary, 2).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+struct Statement<'a> {
+    line: usize,
+    mnemonic: String,
+    operands: Vec<Operand<'a>>,
+}
+
+impl Statement<'_> {
+    /**
+     * How many bytes the statement assembles to.
+     */
+    fn size(&self) -> usize {
+        match self.mnemonic.as_str() {
+            "DB" => self.operands.len(),
+            "DW" => self.operands.len() * 2,
+            _ => 2,
+        }
+    }
+}
+
+/**
+ * Assembles CHIP-8 source using the mnemonics of Cowgod's specification, as printed by the
+ * disassembler, into a program to be loaded at `origin`. Each line holds an instruction,
+ * a `label:`, or both; `;` starts a comment. `DB` and `DW` emit data bytes and big-endian
+ * words. Numbers are decimal, `0x` hex or `0b` binary, and labels can be used wherever an
+ * address or number is expected.
+ */
+pub fn assemble(source: &str, origin: u16) -> Result<Vec<u8>, String> {
+    let mut labels = HashMap::new();
+    let mut statements = vec![];
+    let mut address = origin as usize;
+    for (idx, line) in source.lines().enumerate() {
+        let mut line = line.split(';').next().unwrap().trim();
+        if let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+            if label.is_empty() || !label.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("line {}: invalid label {:?}", idx + 1, label));
+            }
+            if labels.insert(label, address as u16).is_some() {
+                return Err(format!("line {}: label {} defined twice", idx + 1, label));
+            }
+            line = rest.trim();
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let (mnemonic, operands) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let operands = operands
+            .split(',')
+            .map(str::trim)
+            .filter(|operand| !operand.is_empty())
+            .map(Operand::parse)
+            .collect();
+        let statement = Statement {
+            line: idx + 1,
+            mnemonic: mnemonic.to_ascii_uppercase(),
+            operands,
+        };
+        address += statement.size();
+        statements.push(statement);
+    }
+
+    let mut program = vec![];
+    for statement in statements {
+        encode(&statement, &labels, &mut program)
+            .map_err(|e| format!("line {}: {}", statement.line, e))?;
+    }
+    Ok(program)
+}
+
+fn encode(
+    statement: &Statement,
+    labels: &HashMap<&str, u16>,
+    program: &mut Vec<u8>,
+) -> Result<(), String> {
+    use Operand::*;
+
+    let value = |text: &str, max: u16| {
+        let value = parse_number(text)
+            .or_else(|| labels.get(text).copied())
+            .ok_or(format!("unknown label or invalid number {:?}", text))?;
+        if value > max {
+            return Err(format!("{} doesn't fit in {:#x}", text, max));
+        }
+        Ok(value)
+    };
+    let addr = |text| value(text, 0xFFF);
+    let byte = |text| value(text, 0xFF);
+
+    let mnemonic = statement.mnemonic.as_str();
+    let opcode = match (mnemonic, &statement.operands[..]) {
+        ("DB", operands) | ("DW", operands) => {
+            for operand in operands {
+                let Value(text) = operand else {
+                    return Err(format!("{} expects numbers", mnemonic));
+                };
+                if mnemonic == "DB" {
+                    program.push(byte(text)? as u8);
+                } else {
+                    program.extend(value(text, 0xFFFF)?.to_be_bytes());
+                }
+            }
+            return Ok(());
+        }
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("JP", [Value(a)]) => 0x1000 | addr(a)?,
+        ("JP", [Register(0), Value(a)]) => 0xB000 | addr(a)?,
+        ("CALL", [Value(a)]) => 0x2000 | addr(a)?,
+        ("SE", [Register(x), Value(b)]) => 0x3000 | x << 8 | byte(b)?,
+        ("SE", [Register(x), Register(y)]) => 0x5000 | x << 8 | y << 4,
+        ("SNE", [Register(x), Value(b)]) => 0x4000 | x << 8 | byte(b)?,
+        ("SNE", [Register(x), Register(y)]) => 0x9000 | x << 8 | y << 4,
+        ("LD", [Register(x), Value(b)]) => 0x6000 | x << 8 | byte(b)?,
+        ("LD", [Register(x), Register(y)]) => 0x8000 | x << 8 | y << 4,
+        ("LD", [I, Value(a)]) => 0xA000 | addr(a)?,
+        ("LD", [Register(x), DelayTimer]) => 0xF007 | x << 8,
+        ("LD", [Register(x), Key]) => 0xF00A | x << 8,
+        ("LD", [DelayTimer, Register(x)]) => 0xF015 | x << 8,
+        ("LD", [SoundTimer, Register(x)]) => 0xF018 | x << 8,
+        ("LD", [Font, Register(x)]) => 0xF029 | x << 8,
+        ("LD", [Bcd, Register(x)]) => 0xF033 | x << 8,
+        ("LD", [AtI, Register(x)]) => 0xF055 | x << 8,
+        ("LD", [Register(x), AtI]) => 0xF065 | x << 8,
+        ("ADD", [Register(x), Value(b)]) => 0x7000 | x << 8 | byte(b)?,
+        ("ADD", [Register(x), Register(y)]) => 0x8004 | x << 8 | y << 4,
+        ("ADD", [I, Register(x)]) => 0xF01E | x << 8,
+        ("OR", [Register(x), Register(y)]) => 0x8001 | x << 8 | y << 4,
+        ("AND", [Register(x), Register(y)]) => 0x8002 | x << 8 | y << 4,
+        ("XOR", [Register(x), Register(y)]) => 0x8003 | x << 8 | y << 4,
+        ("SUB", [Register(x), Register(y)]) => 0x8005 | x << 8 | y << 4,
+        ("SHR", [Register(x)]) => 0x8006 | x << 8,
+        ("SHR", [Register(x), Register(y)]) => 0x8006 | x << 8 | y << 4,
+        ("SUBN", [Register(x), Register(y)]) => 0x8007 | x << 8 | y << 4,
+        ("SHL", [Register(x)]) => 0x800E | x << 8,
+        ("SHL", [Register(x), Register(y)]) => 0x800E | x << 8 | y << 4,
+        ("RND", [Register(x), Value(b)]) => 0xC000 | x << 8 | byte(b)?,
+        ("DRW", [Register(x), Register(y), Value(n)]) => 0xD000 | x << 8 | y << 4 | value(n, 0xF)?,
+        ("SKP", [Register(x)]) => 0xE09E | x << 8,
+        ("SKNP", [Register(x)]) => 0xE0A1 | x << 8,
+        _ => {
+            return Err(format!(
+                "unknown instruction {} with {} operand(s)",
+                mnemonic,
+                statement.operands.len()
+            ))
+        }
+    };
+    program.extend(opcode.to_be_bytes());
+    Ok(())
+}
+
+/**
+ * Disassembles a program loaded at `origin` into source that assembles back to the same
+ * bytes, with each line's address and bytes in a comment. Words that only decode loosely,
+ * e.g. `0x0120` as `CLS`, are kept as `DW`.
+ */
+pub fn disassemble_program(program: &[u8], origin: u16) -> String {
+    let mut source = String::new();
+    for (idx, chunk) in program.chunks(2).enumerate() {
+        let address = origin as usize + idx * 2;
+        let line = match chunk {
+            [upper, lower] => {
+                let opcode = u16::from_be_bytes([*upper, *lower]);
+                let instruction = disassemble(opcode);
+                match assemble(&instruction, address as u16) {
+                    Ok(bytes) if bytes == chunk => instruction,
+                    _ => format!("DW {:#06x}", opcode),
+                }
+            }
+            _ => format!("DB {:#04x}", chunk[0]),
+        };
+        let bytes: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+        source += &format!("{:<20}; {:#05x}: {}\n", line, address, bytes.join(" "));
+    }
+    source
+}
+
+#[cfg(test)]
+#[path = "./assemblers_test.rs"]
+mod assemblers_test;
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use crate::assemblers::{assemble, disassemble_program};
+
+    #[test]
+    fn test_assemble() {
+        let source = "
+start:  LD V0, 0x0a     ; counter
+        LD I, sprite
+loop:   DRW V0, V1, 5
+        add v0, -1
+        SE V0, 0
+        JP loop
+        JP start
+sprite: DB 0xF0, 0b10010000, 144
+        DW 0xF000
+";
+        let program = assemble(source, 0x200);
+        assert!(program.is_err());
+
+        let program = assemble(&source.replace("-1", "0xff"), 0x200).unwrap();
+        assert_eq!(
+            program,
+            [
+                0x60, 0x0a, 0xA2, 0x0E, 0xD0, 0x15, 0x70, 0xff, 0x30, 0x00, 0x12, 0x04, 0x12, 0x00,
+                0xF0, 0x90, 0x90, 0xF0, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(assemble("LD V0, 0x100", 0x200)
+            .unwrap_err()
+            .starts_with("line 1:"));
+        assert!(assemble("\nJP nowhere", 0x200)
+            .unwrap_err()
+            .starts_with("line 2:"));
+        assert!(assemble("DRW V0, V1", 0x200).is_err());
+        assert!(assemble("a:\na: CLS", 0x200).is_err());
+    }
+
+    #[test]
+    fn test_disassembly_assembles_back() {
+        let program: Vec<u8> = (0..=0xFFFFu16)
+            .step_by(0x33)
+            .flat_map(|opcode| opcode.to_be_bytes())
+            .chain([0x12])
+            .collect();
+        let source = disassemble_program(&program, 0x200);
+        assert_eq!(assemble(&source, 0x200).unwrap(), program);
+    }
+}
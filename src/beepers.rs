@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::waves::{Tone, ToneGenerator};
+
+// the sample rate of WAV recordings
+pub const WAV_SAMPLE_RATE: u32 = 44_100;
+
+/**
+ * Where the sound timer's beep goes: the speakers, the terminal bell, a file or nowhere.
+ */
+pub trait Beeper {
+    /*
+     * Play a 60 Hz frame of emulated time, with the beep on or off for all of it.
+     */
+    fn play_frame(&mut self, sounding: bool);
+    fn set_muted(&mut self, muted: bool);
+}
+
+impl Beeper for Box<dyn Beeper> {
+    fn play_frame(&mut self, sounding: bool) {
+        (**self).play_frame(sounding);
+    }
+
+    fn set_muted(&mut self, muted: bool) {
+        (**self).set_muted(muted);
+    }
+}
+
+/**
+ * Makes no sound, for running without an audio device.
+ */
+pub struct NullBeeper;
+
+impl Beeper for NullBeeper {
+    fn play_frame(&mut self, _sounding: bool) {}
+
+    fn set_muted(&mut self, _muted: bool) {}
+}
+
+/**
+ * Records the beep to a WAV file, which is written when the beeper is dropped. The
+ * samples are the same as the speakers would have played.
+ */
+pub struct WavBeeper {
+    path: PathBuf,
+    generator: ToneGenerator,
+    samples: Vec<f32>,
+}
+
+impl WavBeeper {
+    pub fn new(path: PathBuf, tone: Tone) -> WavBeeper {
+        WavBeeper {
+            path,
+            generator: ToneGenerator::new(tone, WAV_SAMPLE_RATE),
+            samples: vec![],
+        }
+    }
+}
+
+impl Beeper for WavBeeper {
+    fn play_frame(&mut self, sounding: bool) {
+        self.generator.render_frame(sounding, &mut self.samples);
+    }
+
+    fn set_muted(&mut self, muted: bool) {
+        self.generator.set_muted(muted);
+    }
+}
+
+impl Drop for WavBeeper {
+    fn drop(&mut self) {
+        match fs::write(&self.path, to_wav(&self.samples, WAV_SAMPLE_RATE)) {
+            Ok(()) => eprintln!("saved the sound to {}", self.path.display()),
+            Err(e) => eprintln!("couldn't save the sound to {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/**
+ * Encodes mono samples from -1 to 1 as a 16-bit PCM WAV file.
+ */
+pub fn to_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    const HEADERS_SIZE: usize = 44;
+    let data_size = samples.len() * 2;
+    let mut wav = Vec::with_capacity(HEADERS_SIZE + data_size);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&((HEADERS_SIZE - 8 + data_size) as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, mono
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    // bytes per second, bytes per sample and bits per sample
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(data_size as u32).to_le_bytes());
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+#[path = "./beepers_test.rs"]
+mod beepers_test;
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use crate::beepers::{to_wav, Beeper};
+    use crate::timers::{SoundTimer, TimerActions};
+
+    /**
+     * Records which frames the sound timer beeped in.
+     */
+    #[derive(Default)]
+    struct RecordingBeeper {
+        muted: bool,
+        frames: Vec<bool>,
+    }
+
+    impl Beeper for RecordingBeeper {
+        fn play_frame(&mut self, sounding: bool) {
+            self.frames.push(sounding);
+        }
+
+        fn set_muted(&mut self, muted: bool) {
+            self.muted = muted;
+        }
+    }
+
+    #[test]
+    fn test_sound_timer_beeps_for_as_many_frames_as_set() {
+        let mut timer = SoundTimer::new(RecordingBeeper::default(), false);
+        timer.decrement();
+        timer.set(3);
+        for _ in 0..4 {
+            timer.decrement();
+        }
+        assert_eq!(timer.beeper().frames, [false, true, true, true, false]);
+    }
+
+    #[test]
+    fn test_sound_timer_set_to_zero_stops() {
+        let mut timer = SoundTimer::new(RecordingBeeper::default(), false);
+        timer.set(10);
+        timer.decrement();
+        timer.set(0);
+        timer.decrement();
+        assert_eq!(timer.beeper().frames, [true, false]);
+    }
+
+    #[test]
+    fn test_sound_timer_mutes() {
+        let mut timer = SoundTimer::new(RecordingBeeper::default(), true);
+        assert!(timer.beeper().muted);
+        timer.toggle_mute();
+        assert!(!timer.beeper().muted);
+    }
+
+    #[test]
+    fn test_to_wav() {
+        let wav = to_wav(&[0.0, 1.0, -1.0], 44_100);
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[4..8], &42u32.to_le_bytes());
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(&wav[24..28], &44_100u32.to_le_bytes());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(&wav[40..44], &6u32.to_le_bytes());
+        assert_eq!(&wav[44..], &[0, 0, 0xff, 0x7f, 0x01, 0x80]);
+    }
+}
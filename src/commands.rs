@@ -0,0 +1,330 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use clap::{Args, CommandFactory, Parser, Subcommand};
+
+use crate::configs::Settings;
+use crate::debuggers::Watchpoint;
+use crate::quirks::Quirks;
+use crate::viewports::Viewport;
+
+// how many instructions the debuggers can undo by default
+const DEFAULT_REVERSE_WINDOW: usize = 100_000;
+// a minute of game time
+const DEFAULT_BENCH_FRAMES: u64 = 3600;
+
+#[derive(Parser)]
+#[command(
+    name = "chip8",
+    version,
+    about = "A CHIP-8 emulator",
+    after_help = "`chip8 game.ch8 [options]` is short for `chip8 run game.ch8 [options]`."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Play a ROM")]
+    Run(RunArgs),
+    #[command(about = "Play a ROM in the terminal debugger or the debug window")]
+    Debug(DebugArgs),
+    #[command(about = "Disassemble a ROM into source that `asm` assembles back")]
+    Disasm(DisasmArgs),
+    #[command(about = "Assemble source into a ROM")]
+    Asm(AsmArgs),
+    #[command(about = "Show what the ROM database and the config file say about a ROM")]
+    Info(InfoArgs),
+    #[command(about = "Measure how fast a ROM runs without a window")]
+    Bench(BenchArgs),
+}
+
+impl Cli {
+    /**
+     * Parses the command line, treating `chip8 game.ch8 ...` as `chip8 run game.ch8 ...`.
+     */
+    pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Cli {
+        Cli::parse_from(with_default_command(args.into_iter().collect()))
+    }
+}
+
+/**
+ * Inserts `run` before the first argument if it's neither a subcommand nor an option.
+ */
+fn with_default_command(mut args: Vec<OsString>) -> Vec<OsString> {
+    let command = Cli::command();
+    let is_command = |arg: &OsString| {
+        let arg = arg.to_string_lossy();
+        arg.starts_with('-')
+            || arg == "help"
+            || command.get_subcommands().any(|sub| sub.get_name() == arg)
+    };
+    if args.get(1).is_some_and(|arg| !is_command(arg)) {
+        args.insert(1, OsString::from("run"));
+    }
+    args
+}
+
+/**
+ * Parses an address in hex, e.g. `0x200`, or decimal.
+ */
+pub fn parse_address(text: &str) -> Result<u16, String> {
+    let address = match text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    };
+    address
+        .filter(|address| *address < 0x1000)
+        .ok_or(format!("invalid address {:?}, expected e.g. 0x200", text))
+}
+
+/**
+ * Parses `file@address`, a file to load into memory at the address.
+ */
+pub fn parse_preload(text: &str) -> Result<(PathBuf, u16), String> {
+    let (path, address) = text
+        .rsplit_once('@')
+        .filter(|(path, _)| !path.is_empty())
+        .ok_or(format!("invalid {:?}, expected file@address", text))?;
+    Ok((PathBuf::from(path), parse_address(address)?))
+}
+
+fn parse_speed(text: &str) -> Result<u32, String> {
+    text.parse().ok().filter(|speed| *speed >= 1).ok_or(format!(
+        "invalid speed {:?}, expected a whole number from 1",
+        text
+    ))
+}
+
+fn parse_half_life(text: &str) -> Result<f32, String> {
+    text.parse()
+        .ok()
+        .filter(|half_life: &f32| *half_life >= 0.0)
+        .ok_or(format!(
+            "invalid half-life {:?}, expected milliseconds",
+            text
+        ))
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    #[arg(help = "The ROM to play, a .zip or .gz with it, or - for stdin")]
+    pub rom: String,
+    #[arg(long, help = "Which ROM to play from a zip archive holding several")]
+    pub entry: Option<String>,
+
+    #[arg(long, value_parser = parse_speed, help = "Instructions per frame [default: 16]")]
+    pub speed: Option<u32>,
+    #[arg(long, value_parser = Quirks::parse, help = "vip, schip, none or a list like shift,load-store,jump,vf-reset,clip")]
+    pub quirks: Option<Quirks>,
+    #[arg(
+        long,
+        help = "Play as on a platform of the ROM database, e.g. originalChip8 or modernChip8"
+    )]
+    pub platform: Option<String>,
+    #[arg(long, value_parser = parse_address, help = "Where to load the ROM and start it, e.g. 0x600 for ETI-660 programs [default: 0x200]")]
+    pub start_address: Option<u16>,
+    #[arg(
+        long,
+        help = "chip48, vip, dream6800, eti660, schip, octo or a font file [default: chip48]"
+    )]
+    pub font: Option<String>,
+    #[arg(long, value_parser = parse_address, help = "Where to load the hex font, e.g. 0x050 [default: 0x000]")]
+    pub font_address: Option<u16>,
+    #[arg(long, value_name = "FILE@ADDRESS", value_parser = parse_preload, help = "Load data into memory along with the ROM")]
+    pub load: Vec<(PathBuf, u16)>,
+    #[arg(long, help = "Seed for RND, to make runs repeatable")]
+    pub seed: Option<u64>,
+
+    #[arg(long, help = "A theme or 2 or 4 colours like #000000,#ffffff")]
+    pub palette: Option<String>,
+    #[arg(long, value_parser = parse_speed, help = "Window pixels per game pixel [default: 20]")]
+    pub scale: Option<u32>,
+    #[arg(long, help = "integer or fit")]
+    pub scaling: Option<String>,
+    #[arg(long, value_parser = Viewport::parse_rotation, help = "0, 90, 180 or 270")]
+    pub rotate: Option<u16>,
+    #[arg(long, help = "h, v, hv or none")]
+    pub flip: Option<String>,
+    #[arg(long, help = "Draw lines between the pixels")]
+    pub grid: bool,
+    #[arg(long)]
+    pub fullscreen: bool,
+    #[arg(long)]
+    pub vsync: bool,
+    #[arg(long, help = "none, scale2x, epx, scale3x, hq2x, scanlines or crt")]
+    pub filter: Option<String>,
+    #[arg(long, value_parser = parse_half_life, help = "Phosphor half-life in milliseconds, 0 for off")]
+    pub phosphor: Option<f32>,
+    #[arg(long, help = "or or average, optionally with :frames, or none")]
+    pub blend: Option<String>,
+    #[arg(long, help = "Show a clickable keypad: side, overlay or none")]
+    pub keypad: Option<String>,
+    #[arg(
+        long,
+        help = "Play in the terminal: halfblock, braille, sixel, kitty or auto"
+    )]
+    pub terminal: Option<String>,
+    #[arg(
+        long,
+        help = "Run without a window, sound or input as fast as possible, and print the screen at the end"
+    )]
+    pub headless: bool,
+    #[arg(
+        long,
+        requires = "headless",
+        help = "Stop a headless run after this many frames"
+    )]
+    pub frames: Option<u64>,
+    #[arg(long, help = "Start with the sound muted")]
+    pub mute: bool,
+    #[arg(long, help = "Write the sound to a WAV file instead of playing it")]
+    pub wav: Option<PathBuf>,
+    #[arg(long, help = "Pitch of the beep in Hz [default: 440]")]
+    pub frequency: Option<f32>,
+    #[arg(long, help = "Volume of the beep from 0 to 1 [default: 0.25]")]
+    pub volume: Option<f32>,
+    #[arg(long, help = "square, pulse[:duty], triangle, sine or noise")]
+    pub waveform: Option<String>,
+
+    #[arg(long, help = "default, cosmac, two-player or a keymap file")]
+    pub keymap: Option<String>,
+    #[arg(long, help = "A controller profile")]
+    pub controller: Option<String>,
+    #[arg(long, help = "A hotkeys file")]
+    pub hotkeys: Option<String>,
+    #[arg(long, value_parser = parse_speed, help = "How many times faster fast-forward runs [default: 4]")]
+    pub fast_forward: Option<u32>,
+    #[arg(long, value_parser = parse_speed, help = "How many times faster turbo runs [default: 2]")]
+    pub turbo: Option<u32>,
+
+    #[arg(long, help = "The config file [default: ~/.config/chip8/config.toml]")]
+    pub config: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "A programs.json from the CHIP-8 database to use instead of the built-in one"
+    )]
+    pub database: Option<String>,
+    #[arg(
+        long,
+        help = "Write an execution profile to this file when the game ends"
+    )]
+    pub profile: Option<String>,
+    #[arg(long, value_name = "START[-END][:r|w|rw]", value_parser = Watchpoint::parse, help = "Report accesses to these addresses")]
+    pub watch: Vec<Watchpoint>,
+}
+
+impl RunArgs {
+    /**
+     * The settings given on the command line, which override the config file.
+     */
+    pub fn settings(&self) -> Settings {
+        Settings {
+            speed: self.speed,
+            palette: self.palette.clone(),
+            keymap: self.keymap.clone(),
+            controller: self.controller.clone(),
+            hotkeys: self.hotkeys.clone(),
+            filter: self.filter.clone(),
+            scaling: self.scaling.clone(),
+            scale: self.scale,
+            rotate: self.rotate,
+            flip: self.flip.clone(),
+            grid: self.grid.then_some(true),
+            fullscreen: self.fullscreen.then_some(true),
+            vsync: self.vsync.then_some(true),
+            phosphor: self.phosphor,
+            blend: self.blend.clone(),
+            keypad: self.keypad.clone(),
+            fast_forward: self.fast_forward,
+            turbo: self.turbo,
+            quirks: self.quirks,
+            start_address: self.start_address,
+            font: self.font.clone(),
+            font_address: self.font_address,
+            frequency: self.frequency,
+            volume: self.volume,
+            waveform: self.waveform.clone(),
+            mute: self.mute.then_some(true),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct DebugArgs {
+    #[command(flatten)]
+    pub run: RunArgs,
+    #[arg(
+        long,
+        help = "Open the debug window instead of debugging in the terminal"
+    )]
+    pub window: bool,
+    #[arg(long, default_value_t = DEFAULT_REVERSE_WINDOW, help = "How many instructions can be undone")]
+    pub reverse_window: usize,
+}
+
+#[derive(Args)]
+pub struct DisasmArgs {
+    #[arg(help = "The ROM to disassemble, a .zip or .gz with it, or - for stdin")]
+    pub rom: String,
+    #[arg(
+        long,
+        help = "Which ROM to disassemble from a zip archive holding several"
+    )]
+    pub entry: Option<String>,
+    #[arg(long, value_parser = parse_address, default_value = "0x200", help = "Where the ROM is loaded")]
+    pub start_address: u16,
+}
+
+#[derive(Args)]
+pub struct AsmArgs {
+    #[arg(help = "The source to assemble")]
+    pub source: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "Where to write the ROM [default: the source with a .ch8 extension]"
+    )]
+    pub output: Option<PathBuf>,
+    #[arg(long, value_parser = parse_address, default_value = "0x200", help = "Where the ROM is loaded")]
+    pub start_address: u16,
+}
+
+#[derive(Args)]
+pub struct InfoArgs {
+    #[arg(help = "The ROM to look up, a .zip or .gz with it, or - for stdin")]
+    pub rom: String,
+    #[arg(long, help = "Which ROM to look up from a zip archive holding several")]
+    pub entry: Option<String>,
+    #[arg(long, help = "The config file [default: ~/.config/chip8/config.toml]")]
+    pub config: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "A programs.json from the CHIP-8 database to use instead of the built-in one"
+    )]
+    pub database: Option<String>,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    #[arg(help = "The ROM to run, a .zip or .gz with it, or - for stdin")]
+    pub rom: String,
+    #[arg(long, help = "Which ROM to run from a zip archive holding several")]
+    pub entry: Option<String>,
+    #[arg(long, default_value_t = DEFAULT_BENCH_FRAMES, help = "How many frames to run")]
+    pub frames: u64,
+    #[arg(long, value_parser = parse_speed, help = "Instructions per frame [default: 16]")]
+    pub speed: Option<u32>,
+    #[arg(long, value_parser = Quirks::parse, help = "vip, schip, none or a list like shift,clip")]
+    pub quirks: Option<Quirks>,
+    #[arg(long, value_parser = parse_address, default_value = "0x200", help = "Where to load the ROM and start it")]
+    pub start_address: u16,
+    #[arg(long, default_value_t = 0, help = "Seed for RND")]
+    pub seed: u64,
+}
+
+#[cfg(test)]
+#[path = "./commands_test.rs"]
+mod commands_test;
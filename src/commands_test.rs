@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use clap::{CommandFactory, Parser};
+
+    use crate::commands::{parse_address, parse_preload, Cli, Command};
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::parse_args(args.iter().map(Into::into))
+    }
+
+    #[test]
+    fn test_cli_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_run_is_the_default_command() {
+        let Command::Run(args) = parse(&["chip8", "pong.ch8", "--speed", "10", "--grid"]).command
+        else {
+            panic!("expected run");
+        };
+        assert_eq!(args.rom, "pong.ch8");
+        let settings = args.settings();
+        assert_eq!(settings.speed, Some(10));
+        assert_eq!(settings.grid, Some(true));
+        assert_eq!(settings.fullscreen, None);
+
+        assert!(matches!(
+            parse(&["chip8", "disasm", "pong.ch8"]).command,
+            Command::Disasm(_)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        assert!(Cli::try_parse_from(["chip8", "run", "pong.ch8", "--speed", "0"]).is_err());
+        assert!(Cli::try_parse_from(["chip8", "run", "pong.ch8", "--quirks", "wrap"]).is_err());
+        assert!(Cli::try_parse_from(["chip8", "run", "pong.ch8", "--frames", "10"]).is_err());
+        assert!(Cli::try_parse_from(["chip8", "run"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(parse_address("0x600"), Ok(0x600));
+        assert_eq!(parse_address("512"), Ok(0x200));
+        assert!(parse_address("0x1000").is_err());
+        assert!(parse_address("start").is_err());
+    }
+
+    #[test]
+    fn test_parse_preload() {
+        assert_eq!(
+            parse_preload("data/levels.bin@0x800"),
+            Ok((PathBuf::from("data/levels.bin"), 0x800))
+        );
+        assert!(parse_preload("levels.bin").is_err());
+        assert!(parse_preload("@0x800").is_err());
+        assert!(parse_preload("levels.bin@0x1000").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use toml::{Table, Value};
+
+use crate::quirks::Quirks;
+
+/**
+ * The settings that can differ between games. Unset ones keep their built-in defaults.
+ */
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    // instructions per frame
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keymap: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub controller: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hotkeys: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scaling: Option<String>,
+    // window pixels per game pixel when the window opens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fullscreen: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vsync: Option<bool>,
+    // half-life in milliseconds, 0 for off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phosphor: Option<f32>,
+    // `none` for off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blend: Option<String>,
+    // `none` for no on-screen keypad
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keypad: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fast_forward: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turbo: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quirks: Option<Quirks>,
+    // where the ROM is loaded and started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_address: Option<u16>,
+    // a built-in font or a font file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    // where the hex font is loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_address: Option<u16>,
+    // of the beep, in Hz
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<f32>,
+    // from 0 to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waveform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mute: Option<bool>,
+}
+
+impl Settings {
+    /**
+     * The settings that are set, for merging them over others.
+     */
+    pub fn to_table(&self) -> Table {
+        match Value::try_from(self) {
+            Ok(Value::Table(table)) => table,
+            _ => Table::new(),
+        }
+    }
+
+    /**
+     * These settings, overridden by the ones set in `overrides`.
+     */
+    pub fn overridden_by(&self, overrides: &Settings) -> Settings {
+        let mut table = self.to_table();
+        merge(&mut table, overrides.to_table());
+        Value::Table(table)
+            .try_into()
+            .expect("merged settings are still settings")
+    }
+}
+
+/**
+ * Merges `overrides` into `base`, replacing values and merging tables key by key.
+ */
+pub fn merge(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overrides)) => merge(base, overrides),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/**
+ * The SHA-1 of a ROM in lowercase hex, which identifies it in the config whatever the
+ * file is called.
+ */
+pub fn rom_hash(rom: &[u8]) -> String {
+    Sha1::digest(rom)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/**
+ * The config file: global defaults in `[defaults]` and per-ROM settings in
+ * `[roms."<sha1 or file name>"]`.
+ */
+pub struct Config {
+    pub path: PathBuf,
+    defaults: Table,
+    roms: Table,
+}
+
+impl Config {
+    /**
+     * `$XDG_CONFIG_HOME/chip8/config.toml`, or under `~/.config` if that isn't set.
+     */
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join("chip8").join("config.toml"))
+    }
+
+    /**
+     * Loads the config at `path`, which is empty if there is no such file yet.
+     */
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        Config::parse(&text, path).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str, path: &Path) -> Result<Config, String> {
+        let mut table: Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+        let mut section = |name: &str| match table.remove(name) {
+            Some(Value::Table(section)) => Ok(section),
+            Some(_) => Err(format!("[{}] must be a table", name)),
+            None => Ok(Table::new()),
+        };
+        let defaults = section("defaults")?;
+        let roms = section("roms")?;
+        if let Some(key) = table.keys().next() {
+            return Err(format!(
+                "unknown key {:?}, settings go in [defaults] or [roms.\"<sha1>\"]",
+                key
+            ));
+        }
+        Ok(Config {
+            path: path.to_path_buf(),
+            defaults,
+            roms,
+        })
+    }
+
+    /**
+     * The settings for a ROM: the defaults, overridden by what the ROM database
+     * recommends, then by the section for its file name, then by the one for its hash and
+     * finally by `overrides` from the command line.
+     */
+    pub fn settings(
+        &self,
+        hash: &str,
+        file_name: &str,
+        recommended: &Settings,
+        overrides: &Settings,
+    ) -> Result<Settings, String> {
+        let mut table = self.defaults.clone();
+        merge(&mut table, recommended.to_table());
+        for key in [file_name, hash] {
+            match self.roms.get(key) {
+                Some(Value::Table(section)) => merge(&mut table, section.clone()),
+                Some(_) => return Err(format!("[roms.{:?}] must be a table", key)),
+                None => (),
+            }
+        }
+        merge(&mut table, overrides.to_table());
+        Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| format!("{}: {}", self.path.display(), e.message()))
+    }
+}
+
+/**
+ * Where to save the settings of the loaded ROM.
+ */
+pub struct SettingsStore {
+    pub path: PathBuf,
+    pub hash: String,
+    // the settings the ROM was started with
+    pub settings: Settings,
+}
+
+impl SettingsStore {
+    /**
+     * Writes `settings` to the ROM's section in the config file, keyed by its hash,
+     * keeping the rest of the file and its comments as they were.
+     */
+    pub fn save(&self, settings: &Settings) -> Result<(), String> {
+        let error = |e: &dyn std::fmt::Display| format!("{}: {}", self.path.display(), e);
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(error(&e)),
+        };
+        let mut document: toml_edit::DocumentMut = text.parse().map_err(|e| error(&e))?;
+        let section = toml_edit::ser::to_document(settings).map_err(|e| error(&e))?;
+        let roms = document.entry("roms").or_insert_with(|| {
+            let mut roms = toml_edit::Table::new();
+            roms.set_implicit(true);
+            toml_edit::Item::Table(roms)
+        });
+        roms.as_table_mut()
+            .ok_or_else(|| error(&"[roms] must be a table"))?
+            .insert(
+                &self.hash,
+                toml_edit::Item::Table(section.as_table().clone()),
+            );
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| error(&e))?;
+        }
+        fs::write(&self.path, document.to_string()).map_err(|e| error(&e))
+    }
+}
+
+#[cfg(test)]
+#[path = "./configs_test.rs"]
+mod configs_test;
@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::configs::{rom_hash, Config, Settings, SettingsStore};
+    use crate::quirks::Quirks;
+
+    const CONFIG: &str = r#"
+[defaults]
+speed = 10
+palette = "amber"
+quirks = { shift = true }
+
+[roms."pong.ch8"]
+speed = 12
+keymap = "cosmac"
+
+[roms."a9993e364706816aba3e25717850c26c9cd0d89d"]
+speed = 20
+quirks = { clip = true }
+"#;
+
+    fn config() -> Config {
+        Config::parse(CONFIG, Path::new("config.toml")).unwrap()
+    }
+
+    #[test]
+    fn test_rom_hash() {
+        assert_eq!(rom_hash(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+
+    #[test]
+    fn test_defaults_only() {
+        let settings = config()
+            .settings(
+                "0000",
+                "tetris.ch8",
+                &Settings::default(),
+                &Settings::default(),
+            )
+            .unwrap();
+        assert_eq!(settings.speed, Some(10));
+        assert_eq!(settings.palette.as_deref(), Some("amber"));
+        assert_eq!(settings.keymap, None);
+    }
+
+    #[test]
+    fn test_rom_sections_override_defaults() {
+        let settings = config()
+            .settings(
+                &rom_hash(b"abc"),
+                "pong.ch8",
+                &Settings::default(),
+                &Settings::default(),
+            )
+            .unwrap();
+        // the hash is more specific than the file name
+        assert_eq!(settings.speed, Some(20));
+        assert_eq!(settings.keymap.as_deref(), Some("cosmac"));
+        assert_eq!(
+            settings.quirks,
+            Some(Quirks {
+                shift: true,
+                clip: true,
+                ..Quirks::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let overrides = Settings {
+            speed: Some(30),
+            palette: Some("lcd".to_string()),
+            ..Settings::default()
+        };
+        let settings = config()
+            .settings("0000", "pong.ch8", &Settings::default(), &overrides)
+            .unwrap();
+        assert_eq!(settings.speed, Some(30));
+        assert_eq!(settings.palette.as_deref(), Some("lcd"));
+        assert_eq!(settings.keymap.as_deref(), Some("cosmac"));
+    }
+
+    #[test]
+    fn test_overridden_by() {
+        let base = Settings {
+            speed: Some(10),
+            palette: Some("amber".to_string()),
+            ..Settings::default()
+        };
+        let overrides = Settings {
+            speed: Some(20),
+            grid: Some(true),
+            ..Settings::default()
+        };
+        let settings = base.overridden_by(&overrides);
+        assert_eq!(settings.speed, Some(20));
+        assert_eq!(settings.palette.as_deref(), Some("amber"));
+        assert_eq!(settings.grid, Some(true));
+    }
+
+    #[test]
+    fn test_recommended_settings_override_defaults_only() {
+        let recommended = Settings {
+            speed: Some(15),
+            palette: Some("lcd".to_string()),
+            ..Settings::default()
+        };
+        let settings = config()
+            .settings("0000", "pong.ch8", &recommended, &Settings::default())
+            .unwrap();
+        assert_eq!(settings.palette.as_deref(), Some("lcd"));
+        assert_eq!(settings.speed, Some(12));
+    }
+
+    #[test]
+    fn test_invalid_configs() {
+        let path = Path::new("config.toml");
+        assert!(Config::parse("speed = 10", path).is_err());
+        let config = Config::parse("[defaults]\nsped = 10", path).unwrap();
+        assert!(config
+            .settings(
+                "0000",
+                "pong.ch8",
+                &Settings::default(),
+                &Settings::default()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_save_keeps_the_rest_of_the_file() {
+        let dir = std::env::temp_dir().join(format!("chip8-config-test-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "# my settings\n[defaults]\nspeed = 10\n").unwrap();
+
+        let store = SettingsStore {
+            path: path.clone(),
+            hash: rom_hash(b"abc"),
+            settings: Settings::default(),
+        };
+        let settings = Settings {
+            speed: Some(15),
+            grid: Some(true),
+            ..Settings::default()
+        };
+        store.save(&settings).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(text.starts_with("# my settings\n"));
+        let config = Config::parse(&text, &path).unwrap();
+        let saved = config
+            .settings(
+                &rom_hash(b"abc"),
+                "pong.ch8",
+                &Settings::default(),
+                &Settings::default(),
+            )
+            .unwrap();
+        assert_eq!(saved.speed, Some(15));
+        assert_eq!(saved.grid, Some(true));
+    }
+}
@@ -0,0 +1,334 @@
+use std::io::{self, BufRead, Write};
+
+use crate::debuggers::{Breakpoint, DebugEvent, Stop, Watchpoint};
+use crate::displays::Drawable;
+use crate::expressions::Expr;
+use crate::input_driver::EventPollable;
+use crate::interpreters::{disassemble, Interpreter, MachineState, Quit};
+use crate::timers::TimerActions;
+
+const HELP: &str = "\
+commands:
+  s, step [n]                 execute n instructions (default 1)
+  c, continue                 run until a breakpoint, watchpoint or event stops execution
+  rs, reverse-step [n]        undo n instructions (default 1)
+  rc, reverse-continue        undo instructions back to a breakpoint or watchpoint
+  b, break [addr] [after n] [if cond]
+                              e.g. `b 0x2a0`, `b after 3 if V3 == 0x10 && I > 0x300`
+  w, watch start[-end][:r|w|rw]
+  e, event collision|cls|sound|keywait|stack|invalid
+  d, delete break|watch <n> | delete event <name>
+  i, info                     list breakpoints, watchpoints and events
+  r, regs                     show registers, stack and timers
+  x addr [len]                hex dump memory
+  l, list [addr] [n]          disassemble n instructions (default around PC)
+  p, print expr               evaluate an expression
+  q, quit";
+
+/**
+ * A terminal debugger reading commands from stdin. Execution starts paused.
+ */
+pub fn debug<D, I, ST, DT>(interpreter: &mut Interpreter<D, I, ST, DT>)
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    println!("{}", HELP);
+    print_location(interpreter);
+    let stdin = io::stdin();
+    loop {
+        print!("(chip8) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let args = args.trim();
+        let result = match command {
+            "" => Ok(()),
+            "h" | "help" => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            "s" | "step" => match parse_count(args, 1) {
+                Ok(n) => {
+                    if step(interpreter, n).is_err() {
+                        return;
+                    }
+                    Ok(())
+                }
+                Err(message) => Err(message),
+            },
+            "c" | "continue" => {
+                if resume(interpreter).is_err() {
+                    return;
+                }
+                Ok(())
+            }
+            "rs" | "reverse-step" => parse_count(args, 1).map(|n| {
+                let undone = (0..n).take_while(|_| interpreter.reverse_step()).count();
+                if undone < n {
+                    println!("reached the start of the journal");
+                }
+                print_location(interpreter);
+            }),
+            "rc" | "reverse-continue" => {
+                match interpreter.reverse_continue() {
+                    Some(stop) => print_stop(&stop, interpreter),
+                    None => println!("reached the start of the journal"),
+                }
+                print_location(interpreter);
+                Ok(())
+            }
+            "b" | "break" => Breakpoint::parse(args).map(|breakpoint| {
+                println!(
+                    "breakpoint {}: {}",
+                    interpreter.debugger().breakpoints().len(),
+                    breakpoint
+                );
+                interpreter.debugger_mut().add_breakpoint(breakpoint);
+            }),
+            "w" | "watch" => Watchpoint::parse(args).map(|watchpoint| {
+                println!(
+                    "watchpoint {}: {}",
+                    interpreter.debugger().watchpoints().len(),
+                    args
+                );
+                interpreter.debugger_mut().add_watchpoint(watchpoint);
+            }),
+            "e" | "event" => DebugEvent::parse(args)
+                .map(|event| interpreter.debugger_mut().add_event_breakpoint(event)),
+            "d" | "delete" => delete(interpreter, args),
+            "i" | "info" => {
+                info(interpreter);
+                Ok(())
+            }
+            "r" | "regs" => {
+                print_registers(&interpreter.state());
+                Ok(())
+            }
+            "x" => hexdump(interpreter, args),
+            "l" | "list" => list(interpreter, args),
+            "p" | "print" => Expr::parse(args).map(|expr| {
+                let value = expr.eval(&interpreter.state(), interpreter.memory());
+                println!("{} = {} ({:#x})", expr, value, value);
+            }),
+            "q" | "quit" => return,
+            _ => Err(format!("unknown command {:?}, try `help`", command)),
+        };
+        if let Err(message) = result {
+            println!("{}", message);
+        }
+        interpreter.present();
+    }
+}
+
+fn parse_count(arg: &str, default: usize) -> Result<usize, String> {
+    if arg.is_empty() {
+        Ok(default)
+    } else {
+        arg.parse().map_err(|_| format!("invalid count {:?}", arg))
+    }
+}
+
+fn step<D, I, ST, DT>(interpreter: &mut Interpreter<D, I, ST, DT>, n: usize) -> Result<(), Quit>
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    interpreter.debugger_mut().resume();
+    for _ in 0..n {
+        if let Some(stop) = interpreter.run_instruction()? {
+            print_stop(&stop, interpreter);
+            break;
+        }
+    }
+    print_location(interpreter);
+    Ok(())
+}
+
+fn resume<D, I, ST, DT>(interpreter: &mut Interpreter<D, I, ST, DT>) -> Result<(), Quit>
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    interpreter.debugger_mut().resume();
+    loop {
+        if let Some(stop) = interpreter.run_frame()? {
+            print_stop(&stop, interpreter);
+            print_location(interpreter);
+            return Ok(());
+        }
+    }
+}
+
+fn delete<D, I, ST, DT>(
+    interpreter: &mut Interpreter<D, I, ST, DT>,
+    args: &str,
+) -> Result<(), String>
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    let (kind, which) = args.split_once(' ').unwrap_or((args, ""));
+    let debugger = interpreter.debugger_mut();
+    let removed = match kind {
+        "break" => which
+            .parse()
+            .ok()
+            .and_then(|idx| debugger.remove_breakpoint(idx))
+            .is_some(),
+        "watch" => which
+            .parse()
+            .ok()
+            .and_then(|idx| debugger.remove_watchpoint(idx))
+            .is_some(),
+        "event" => debugger.remove_event_breakpoint(DebugEvent::parse(which)?),
+        _ => return Err("usage: delete break|watch <n> | delete event <name>".to_string()),
+    };
+    if removed {
+        Ok(())
+    } else {
+        Err(format!("no {} {}", kind, which))
+    }
+}
+
+fn info<D, I, ST, DT>(interpreter: &Interpreter<D, I, ST, DT>)
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    let debugger = interpreter.debugger();
+    for (idx, breakpoint) in debugger.breakpoints().iter().enumerate() {
+        println!("breakpoint {}: {}", idx, breakpoint);
+    }
+    for (idx, watchpoint) in debugger.watchpoints().iter().enumerate() {
+        println!(
+            "watchpoint {}: {:#05x}-{:#05x}{}{}",
+            idx,
+            watchpoint.start,
+            watchpoint.end,
+            if watchpoint.on_read { " read" } else { "" },
+            if watchpoint.on_write { " write" } else { "" }
+        );
+    }
+    for event in debugger.event_breakpoints() {
+        println!("event: {}", event);
+    }
+}
+
+fn hexdump<D, I, ST, DT>(interpreter: &Interpreter<D, I, ST, DT>, args: &str) -> Result<(), String>
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    let mut args = args.split_whitespace();
+    let state = interpreter.state();
+    let memory = interpreter.memory();
+    let start = match args.next() {
+        Some(addr) => Expr::parse(addr)?.eval(&state, memory),
+        None => state.i as i64,
+    };
+    let len = parse_count(args.next().unwrap_or(""), 16)?;
+    let start = start.clamp(0, memory.value.len() as i64) as usize;
+    let end = (start + len).min(memory.value.len());
+    for (row, bytes) in memory.value[start..end].chunks(16).enumerate() {
+        let bytes: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        println!("{:#05x}: {}", start + row * 16, bytes.join(" "));
+    }
+    Ok(())
+}
+
+fn list<D, I, ST, DT>(interpreter: &Interpreter<D, I, ST, DT>, args: &str) -> Result<(), String>
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    let mut args = args.split_whitespace();
+    let state = interpreter.state();
+    let memory = interpreter.memory();
+    let start = match args.next() {
+        Some(addr) => Expr::parse(addr)?.eval(&state, memory),
+        None => state.pc as i64 - 8,
+    };
+    let n = parse_count(args.next().unwrap_or(""), 10)?;
+    let last = memory.value.len() as i64 - 2;
+    for addr in (start.clamp(0, last)..=last).step_by(2).take(n) {
+        let marker = if addr == state.pc as i64 { "=>" } else { "  " };
+        let opcode = memory.read_instruction(addr as u16);
+        println!(
+            "{} {:#05x}: {:04x}  {}",
+            marker,
+            addr,
+            opcode,
+            disassemble(opcode)
+        );
+    }
+    Ok(())
+}
+
+fn print_stop<D, I, ST, DT>(stop: &Stop, interpreter: &Interpreter<D, I, ST, DT>)
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    match stop {
+        Stop::Breakpoint(idx) => {
+            println!(
+                "stopped at breakpoint {}: {}",
+                idx,
+                interpreter.debugger().breakpoints()[*idx]
+            )
+        }
+        _ => println!("stopped at {}", stop),
+    }
+}
+
+fn print_location<D, I, ST, DT>(interpreter: &Interpreter<D, I, ST, DT>)
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    let pc = interpreter.state().pc;
+    let opcode = interpreter.memory().read_instruction(pc);
+    println!("{:#05x}: {:04x}  {}", pc, opcode, disassemble(opcode));
+}
+
+fn print_registers(state: &MachineState) {
+    let v: Vec<String> = state
+        .v
+        .iter()
+        .enumerate()
+        .map(|(x, value)| format!("V{:X}={:02x}", x, value))
+        .collect();
+    println!("{}", v[..8].join(" "));
+    println!("{}", v[8..].join(" "));
+    println!(
+        "I={:#05x} PC={:#05x} SP={} DT={} ST={}",
+        state.i, state.pc, state.sp, state.dt, state.st
+    );
+    let stack: Vec<String> = state.stack[..state.sp]
+        .iter()
+        .map(|addr| format!("{:#05x}", addr))
+        .collect();
+    println!("stack: [{}]", stack.join(", "));
+}
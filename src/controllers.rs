@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use sdl2::controller::{Axis, Button, GameController};
+
+// stick positions closer to the center than this don't press anything, out of 32767
+const DEFAULT_DEADZONE: i16 = 8000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ControllerInput {
+    Button(Button),
+    // an axis pushed towards its positive (right or down) or negative end
+    Axis(Axis, bool),
+}
+
+impl ControllerInput {
+    /**
+     * Parses an SDL game controller button name like `a` or `dpup`, or an axis name
+     * followed by the direction like `leftx+` or `lefty-`.
+     */
+    pub fn parse(name: &str) -> Result<ControllerInput, String> {
+        let axis = |name: &str, positive| {
+            Axis::from_string(name).map(|a| ControllerInput::Axis(a, positive))
+        };
+        let input = match name.strip_suffix('+') {
+            Some(axis_name) => axis(axis_name, true),
+            None => match name.strip_suffix('-') {
+                Some(axis_name) => axis(axis_name, false),
+                None => Button::from_string(name).map(ControllerInput::Button),
+            },
+        };
+        input.ok_or(format!("unknown controller button or axis {:?}", name))
+    }
+}
+
+/**
+ * Which controller buttons and stick directions press which CHIP-8 key. By default the
+ * d-pad and left stick press 2, 4, 6 and 8, the arrows on the keypad, and the face
+ * buttons 5, 0, A and B.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ControllerMap {
+    keys: [Vec<ControllerInput>; 16],
+    pub deadzone: i16,
+}
+
+impl ControllerMap {
+    pub fn new() -> ControllerMap {
+        use ControllerInput::Axis as Stick;
+        use ControllerInput::Button as Pressed;
+
+        let mut keys: [Vec<ControllerInput>; 16] = Default::default();
+        keys[0x2] = vec![Pressed(Button::DPadUp), Stick(Axis::LeftY, false)];
+        keys[0x8] = vec![Pressed(Button::DPadDown), Stick(Axis::LeftY, true)];
+        keys[0x4] = vec![Pressed(Button::DPadLeft), Stick(Axis::LeftX, false)];
+        keys[0x6] = vec![Pressed(Button::DPadRight), Stick(Axis::LeftX, true)];
+        keys[0x5] = vec![Pressed(Button::A)];
+        keys[0x0] = vec![Pressed(Button::B)];
+        keys[0xA] = vec![Pressed(Button::X)];
+        keys[0xB] = vec![Pressed(Button::Y)];
+        keys[0x1] = vec![Pressed(Button::LeftShoulder)];
+        keys[0xC] = vec![Pressed(Button::RightShoulder)];
+        keys[0xE] = vec![Pressed(Button::Back)];
+        keys[0xF] = vec![Pressed(Button::Start)];
+        ControllerMap {
+            keys,
+            deadzone: DEFAULT_DEADZONE,
+        }
+    }
+
+    /**
+     * Parses a controller profile on top of `base`, in the format of keymap files: each
+     * line binds a CHIP-8 key to buttons and axis directions, e.g. `2 = dpup, lefty-`,
+     * and `deadzone = 8000` sets the deadzone of the sticks.
+     */
+    pub fn parse(text: &str, base: ControllerMap) -> Result<ControllerMap, String> {
+        let mut map = base;
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, inputs) = line
+                .split_once('=')
+                .ok_or(format!("line {}: expected `key = buttons`", idx + 1))?;
+            let (key, inputs) = (key.trim(), inputs.trim());
+            if key == "deadzone" {
+                map.deadzone = inputs
+                    .parse()
+                    .ok()
+                    .filter(|deadzone| *deadzone >= 0)
+                    .ok_or(format!("line {}: invalid deadzone {:?}", idx + 1, inputs))?;
+                continue;
+            }
+            let key = match u8::from_str_radix(key, 16) {
+                Ok(key) if key < 16 => key,
+                _ => return Err(format!("line {}: invalid CHIP-8 key {:?}", idx + 1, key)),
+            };
+            map.keys[key as usize] = inputs
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    ControllerInput::parse(name).map_err(|e| format!("line {}: {}", idx + 1, e))
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(map)
+    }
+
+    /**
+     * Loads a controller profile on top of `base`.
+     */
+    pub fn load(path: &str, base: ControllerMap) -> Result<ControllerMap, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        ControllerMap::parse(&text, base).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /**
+     * Moves the d-pad and left stick directions and the A and B buttons to the CHIP-8 keys
+     * a game uses for `up`, `down`, `left`, `right`, `a` and `b`, as the ROM database
+     * lists them. Keys it doesn't mention keep their buttons.
+     */
+    pub fn with_key_hints(self, hints: &BTreeMap<String, u8>) -> ControllerMap {
+        use ControllerInput::Axis as Stick;
+        use ControllerInput::Button as Pressed;
+
+        let mut map = self;
+        for (name, key) in hints {
+            let inputs = match name.as_str() {
+                "up" => vec![Pressed(Button::DPadUp), Stick(Axis::LeftY, false)],
+                "down" => vec![Pressed(Button::DPadDown), Stick(Axis::LeftY, true)],
+                "left" => vec![Pressed(Button::DPadLeft), Stick(Axis::LeftX, false)],
+                "right" => vec![Pressed(Button::DPadRight), Stick(Axis::LeftX, true)],
+                "a" => vec![Pressed(Button::A)],
+                "b" => vec![Pressed(Button::B)],
+                _ => continue,
+            };
+            if *key >= 16 {
+                continue;
+            }
+            for bound in map.keys.iter_mut() {
+                bound.retain(|input| !inputs.contains(input));
+            }
+            map.keys[*key as usize].extend(inputs);
+        }
+        map
+    }
+
+    /**
+     * Applies the controller profile next to the ROM, e.g. `pong.controller` for
+     * `pong.ch8`, if there is one.
+     */
+    pub fn with_rom_overrides(self, rom_path: &str) -> Result<ControllerMap, String> {
+        let path = Path::new(rom_path).with_extension("controller");
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                ControllerMap::parse(&text, self).map_err(|e| format!("{}: {}", path.display(), e))
+            }
+            Err(_) => Ok(self),
+        }
+    }
+
+    pub fn inputs(&self, key: u8) -> &[ControllerInput] {
+        &self.keys[key as usize]
+    }
+
+    /**
+     * Marks the CHIP-8 keys `controller` is pressing in `keypad`.
+     */
+    pub fn press(&self, controller: &GameController, keypad: &mut [bool; 16]) {
+        for (key, inputs) in self.keys.iter().enumerate() {
+            let pressed = inputs.iter().any(|input| match input {
+                ControllerInput::Button(button) => controller.button(*button),
+                ControllerInput::Axis(axis, positive) => {
+                    let value = controller.axis(*axis);
+                    if *positive {
+                        value > self.deadzone
+                    } else {
+                        value < -self.deadzone
+                    }
+                }
+            });
+            if pressed {
+                keypad[key] = true;
+            }
+        }
+    }
+}
+
+impl Default for ControllerMap {
+    fn default() -> Self {
+        ControllerMap::new()
+    }
+}
+
+#[cfg(test)]
+#[path = "./controllers_test.rs"]
+mod controllers_test;
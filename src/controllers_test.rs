@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+    use sdl2::controller::{Axis, Button};
+    use sdl2::sys;
+
+    use crate::controllers::{ControllerInput, ControllerMap};
+    use crate::hotkeys::Hotkeys;
+    use crate::input_driver::{EventPollable, InputDriver, InputEvent};
+    use crate::keymaps::Keymap;
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(
+            ControllerInput::parse("dpup"),
+            Ok(ControllerInput::Button(Button::DPadUp))
+        );
+        assert_eq!(
+            ControllerInput::parse("lefty-"),
+            Ok(ControllerInput::Axis(Axis::LeftY, false))
+        );
+        assert!(ControllerInput::parse("trigger").is_err());
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let map =
+            ControllerMap::parse("deadzone = 1000\n5 = a, righty+\n0 =", ControllerMap::new())
+                .unwrap();
+        assert_eq!(map.deadzone, 1000);
+        assert_eq!(
+            map.inputs(0x5),
+            [
+                ControllerInput::Button(Button::A),
+                ControllerInput::Axis(Axis::RightY, true)
+            ]
+        );
+        assert!(map.inputs(0x0).is_empty());
+        assert_eq!(map.inputs(0x2), ControllerMap::new().inputs(0x2));
+        assert!(ControllerMap::parse("deadzone = -1", ControllerMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_key_hints() {
+        let hints = [("left".to_string(), 0x7), ("a".to_string(), 0x4)].into();
+        let map = ControllerMap::new().with_key_hints(&hints);
+        assert_eq!(
+            map.inputs(0x7),
+            [
+                ControllerInput::Button(Button::DPadLeft),
+                ControllerInput::Axis(Axis::LeftX, false)
+            ]
+        );
+        assert!(map
+            .inputs(0x4)
+            .contains(&ControllerInput::Button(Button::A)));
+        assert!(map.inputs(0x5).is_empty());
+        assert_eq!(map.inputs(0x6), ControllerMap::new().inputs(0x6));
+    }
+
+    // SDL may only be initialized from one thread, so everything using it is one test
+    #[test]
+    fn test_virtual_controller() {
+        let sdl = sdl2::init().unwrap();
+        let mut input = InputDriver::new(
+            &sdl,
+            Keymap::default(),
+            ControllerMap::new(),
+            Hotkeys::new(),
+        );
+
+        // SAFETY: the virtual joystick is opened after attaching and detached at the end.
+        let (device, joystick) = unsafe {
+            let device = sys::SDL_JoystickAttachVirtual(
+                sys::SDL_JoystickType::SDL_JOYSTICK_TYPE_GAMECONTROLLER,
+                sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_MAX as i32,
+                sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_MAX as i32,
+                0,
+            );
+            assert!(device >= 0, "SDL doesn't support virtual joysticks");
+            (device, sys::SDL_JoystickOpen(device))
+        };
+
+        // hot-plugging opens the controller
+        input.poll(0);
+        unsafe {
+            sys::SDL_JoystickSetVirtualButton(
+                joystick,
+                sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_A as i32,
+                1,
+            );
+            sys::SDL_JoystickSetVirtualAxis(
+                joystick,
+                sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTX as i32,
+                i16::MAX,
+            );
+            sys::SDL_JoystickSetVirtualAxis(
+                joystick,
+                sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTY as i32,
+                // within the deadzone
+                -100,
+            );
+        }
+        let events: Vec<_> = input.poll(1).into_iter().map(|timed| timed.event).collect();
+        assert_eq!(events, [InputEvent::KeyDown(0x5), InputEvent::KeyDown(0x6)]);
+
+        unsafe {
+            sys::SDL_JoystickClose(joystick);
+            sys::SDL_JoystickDetachVirtual(device);
+        }
+        // unplugging closes it
+        let events: Vec<_> = input.poll(2).into_iter().map(|timed| timed.event).collect();
+        assert_eq!(events, [InputEvent::KeyUp(0x5), InputEvent::KeyUp(0x6)]);
+    }
+}
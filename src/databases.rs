@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::configs::Settings;
+use crate::quirks::Quirks;
+
+// the community CHIP-8 database (https://github.com/chip-8/chip-8-database), whose
+// programs.json can be copied over this one
+const PROGRAMS: &str = include_str!("../database/programs.json");
+const PLATFORMS: &str = include_str!("../database/platforms.json");
+
+// the platforms that run on this emulator's 64x32 CHIP-8
+const SUPPORTED_PLATFORMS: [&str; 3] = ["originalChip8", "hybridVIP", "modernChip8"];
+
+/**
+ * Quirks as the database names them. Platforms set all of them, ROMs only the ones in
+ * which they differ from their platform.
+ */
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DatabaseQuirks {
+    // 8XY6 and 8XYE shift VX in place
+    shift: Option<bool>,
+    memory_increment_by_x: Option<bool>,
+    memory_leave_i_unchanged: Option<bool>,
+    // sprites wrap around the edges
+    wrap: Option<bool>,
+    jump: Option<bool>,
+    logic: Option<bool>,
+}
+
+impl DatabaseQuirks {
+    fn or(self, base: DatabaseQuirks) -> DatabaseQuirks {
+        DatabaseQuirks {
+            shift: self.shift.or(base.shift),
+            memory_increment_by_x: self.memory_increment_by_x.or(base.memory_increment_by_x),
+            memory_leave_i_unchanged: self
+                .memory_leave_i_unchanged
+                .or(base.memory_leave_i_unchanged),
+            wrap: self.wrap.or(base.wrap),
+            jump: self.jump.or(base.jump),
+            logic: self.logic.or(base.logic),
+        }
+    }
+
+    /**
+     * The closest quirks this emulator has. Incrementing I by X rather than X + 1 is
+     * treated like incrementing it by X + 1.
+     */
+    fn quirks(self) -> Quirks {
+        Quirks {
+            shift: !self.shift.unwrap_or(false),
+            load_store: !self.memory_leave_i_unchanged.unwrap_or(false),
+            jump: self.jump.unwrap_or(false),
+            vf_reset: self.logic.unwrap_or(false),
+            clip: !self.wrap.unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Platform {
+    pub id: String,
+    pub name: String,
+    default_tickrate: Option<u32>,
+    #[serde(default)]
+    quirks: DatabaseQuirks,
+}
+
+impl Platform {
+    pub fn is_supported(&self) -> bool {
+        SUPPORTED_PLATFORMS.contains(&self.id.as_str())
+    }
+
+    /**
+     * Where the memory programs can use ends. The COSMAC VIP's interpreter keeps its stack
+     * and the display at the top of its 4 KB.
+     */
+    pub fn memory_end(&self) -> usize {
+        match self.id.as_str() {
+            "originalChip8" | "hybridVIP" => 0xEA0,
+            "xochip" => 0x10000,
+            "megachip8" => 0x1000000,
+            _ => 0x1000,
+        }
+    }
+
+    /**
+     * The built-in font the platform's interpreter had.
+     */
+    pub fn font(&self) -> Option<&'static str> {
+        match self.id.as_str() {
+            "originalChip8" | "hybridVIP" => Some("vip"),
+            "chip48" => Some("chip48"),
+            "superchip1" | "superchip" => Some("schip"),
+            "xochip" => Some("octo"),
+            _ => None,
+        }
+    }
+
+    /**
+     * The speed, quirks and font of the platform, for playing a ROM as if written for it.
+     */
+    pub fn settings(&self) -> Settings {
+        Settings {
+            speed: self.default_tickrate,
+            quirks: Some(self.quirks.quirks()),
+            font: self.font().map(String::from),
+            ..Settings::default()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Colors {
+    #[serde(default)]
+    pixels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Rom {
+    #[serde(default)]
+    platforms: Vec<String>,
+    #[serde(default)]
+    quirky_platforms: HashMap<String, DatabaseQuirks>,
+    tickrate: Option<u32>,
+    colors: Option<Colors>,
+    #[serde(default)]
+    keys: BTreeMap<String, u8>,
+}
+
+#[derive(Deserialize)]
+struct Program {
+    title: String,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    roms: HashMap<String, Rom>,
+}
+
+/**
+ * What the database knows about a ROM.
+ */
+pub struct KnownRom {
+    pub title: String,
+    pub authors: Vec<String>,
+    // the platform the ROM is best played on, the first one the database lists
+    pub platform: Option<Platform>,
+    pub quirks: Option<Quirks>,
+    // instructions per frame
+    pub speed: Option<u32>,
+    pub palette: Option<String>,
+    // which CHIP-8 keys act as `up`, `down`, `left`, `right`, `a` and `b`
+    pub keys: BTreeMap<String, u8>,
+}
+
+impl KnownRom {
+    /**
+     * The title with the authors, for the window title.
+     */
+    pub fn describe(&self) -> String {
+        if self.authors.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} by {}", self.title, self.authors.join(", "))
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.platform.as_ref().is_none_or(Platform::is_supported)
+    }
+
+    /**
+     * The settings the database recommends, which the config and command line override.
+     */
+    pub fn settings(&self) -> Settings {
+        Settings {
+            speed: self.speed,
+            palette: self.palette.clone(),
+            quirks: self.quirks,
+            font: self
+                .platform
+                .as_ref()
+                .and_then(Platform::font)
+                .map(String::from),
+            ..Settings::default()
+        }
+    }
+}
+
+pub struct Database {
+    programs: Vec<Program>,
+    platforms: Vec<Platform>,
+}
+
+impl Database {
+    /**
+     * The database built into the emulator.
+     */
+    pub fn embedded() -> Database {
+        Database::parse(PROGRAMS, PLATFORMS).unwrap()
+    }
+
+    /**
+     * Loads a programs.json from the community database, with the built-in platforms.
+     */
+    pub fn load(path: &str) -> Result<Database, String> {
+        let programs = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Database::parse(&programs, PLATFORMS).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(programs: &str, platforms: &str) -> Result<Database, String> {
+        Ok(Database {
+            programs: serde_json::from_str(programs).map_err(|e| e.to_string())?,
+            platforms: serde_json::from_str(platforms).map_err(|e| e.to_string())?,
+        })
+    }
+
+    /**
+     * Looks up a platform by its id in the database, e.g. `originalChip8` or `superchip`,
+     * ignoring case.
+     */
+    pub fn platform(&self, id: &str) -> Result<&Platform, String> {
+        self.platforms
+            .iter()
+            .find(|platform| platform.id.eq_ignore_ascii_case(id))
+            .ok_or_else(|| {
+                let ids: Vec<&str> = self.platforms.iter().map(|p| p.id.as_str()).collect();
+                format!(
+                    "unknown platform {:?}, expected one of {}",
+                    id,
+                    ids.join(", ")
+                )
+            })
+    }
+
+    /**
+     * Looks up a ROM by the SHA-1 of its contents.
+     */
+    pub fn find(&self, hash: &str) -> Option<KnownRom> {
+        let (program, rom) = self
+            .programs
+            .iter()
+            .find_map(|program| Some((program, program.roms.get(hash)?)))?;
+        let platform = rom
+            .platforms
+            .first()
+            .and_then(|id| self.platforms.iter().find(|platform| platform.id == *id));
+        let quirks = platform.map(|platform| {
+            let quirky = rom.quirky_platforms.get(&platform.id).copied();
+            quirky.unwrap_or_default().or(platform.quirks).quirks()
+        });
+        let palette = rom
+            .colors
+            .as_ref()
+            .and_then(|colors| match colors.pixels.len() {
+                0 | 1 => None,
+                2 | 3 => Some(colors.pixels[..2].join(",")),
+                _ => Some(colors.pixels[..4].join(",")),
+            });
+        Some(KnownRom {
+            title: program.title.clone(),
+            authors: program.authors.clone(),
+            platform: platform.cloned(),
+            quirks,
+            speed: rom
+                .tickrate
+                .or(platform.and_then(|platform| platform.default_tickrate)),
+            palette,
+            keys: rom.keys.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "./databases_test.rs"]
+mod databases_test;
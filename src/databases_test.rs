@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use crate::configs::rom_hash;
+    use crate::databases::{Database, PLATFORMS};
+    use crate::quirks::Quirks;
+
+    const PROGRAMS: &str = r##"[
+  {
+    "title": "Test Game",
+    "authors": ["Ann", "Bob"],
+    "roms": {
+      "a9993e364706816aba3e25717850c26c9cd0d89d": {
+        "file": "test.ch8",
+        "platforms": ["originalChip8", "modernChip8"],
+        "quirkyPlatforms": { "originalChip8": { "logic": false } },
+        "colors": { "pixels": ["#000000", "#ffaa00"] },
+        "keys": { "left": 4, "right": 6 }
+      },
+      "0000000000000000000000000000000000000000": {
+        "platforms": ["superchip"],
+        "tickrate": 50
+      }
+    }
+  }
+]"##;
+
+    #[test]
+    fn test_embedded_database_parses() {
+        Database::embedded();
+    }
+
+    #[test]
+    fn test_find() {
+        let database = Database::parse(PROGRAMS, PLATFORMS).unwrap();
+        let rom = database.find(&rom_hash(b"abc")).unwrap();
+        assert_eq!(rom.describe(), "Test Game by Ann, Bob");
+        assert!(rom.is_supported());
+        // the platform's tickrate, and its quirks but for the one the ROM changes
+        assert_eq!(rom.speed, Some(15));
+        assert_eq!(
+            rom.quirks,
+            Some(Quirks {
+                vf_reset: false,
+                ..Quirks::parse("vip").unwrap()
+            })
+        );
+        assert_eq!(rom.palette.as_deref(), Some("#000000,#ffaa00"));
+        assert_eq!(rom.keys.get("left"), Some(&4));
+        assert_eq!(rom.settings().font.as_deref(), Some("vip"));
+
+        assert!(database.find("1234").is_none());
+    }
+
+    #[test]
+    fn test_unsupported_platform() {
+        let database = Database::parse(PROGRAMS, PLATFORMS).unwrap();
+        let rom = database
+            .find("0000000000000000000000000000000000000000")
+            .unwrap();
+        assert!(!rom.is_supported());
+        assert_eq!(rom.speed, Some(50));
+        assert_eq!(rom.quirks, Some(Quirks::parse("schip").unwrap()));
+    }
+}
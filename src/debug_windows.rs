@@ -0,0 +1,384 @@
+extern crate sdl2;
+
+use std::time::Duration;
+
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use sdl2::VideoSubsystem;
+
+use crate::displays::Drawable;
+use crate::glyphs::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::input_driver::EventPollable;
+use crate::interpreters::{disassemble, Interpreter};
+use crate::timers::TimerActions;
+
+const SCALE: u32 = 2;
+const LINE_HEIGHT: i32 = (GLYPH_HEIGHT * SCALE + 4) as i32;
+const WINDOW_WIDTH: u32 = 760;
+const WINDOW_HEIGHT: u32 = 600;
+
+const DISASSEMBLY_LINES: i32 = 21;
+const HEX_ROWS: i32 = 16;
+const HEX_TOP: i32 = 352;
+// bytes from I on that are highlighted, enough for the largest sprite
+const I_HIGHLIGHT_LEN: u16 = 15;
+
+const BACKGROUND_COLOR: Color = Color::RGB(20, 20, 28);
+const TEXT_COLOR: Color = Color::RGB(200, 200, 200);
+const LABEL_COLOR: Color = Color::RGB(120, 160, 220);
+const HIGHLIGHT_COLOR: Color = Color::RGB(70, 70, 30);
+const PC_COLOR: Color = Color::RGB(40, 80, 40);
+const KEY_DOWN_COLOR: Color = Color::GREEN;
+
+// the layout of the hex keypad on the COSMAC VIP
+const KEYPAD_LAYOUT: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Control {
+    Run,
+    Pause,
+    Step,
+    ReverseStep,
+    Close,
+}
+
+const BUTTONS: [(Control, &str, i32); 4] = [
+    (Control::Run, "RUN F5", 8),
+    (Control::Pause, "PAUSE SPACE", 80),
+    (Control::Step, "STEP F10", 184),
+    (Control::ReverseStep, "BACK F9", 264),
+];
+
+/**
+ * A second window showing the machine state while the game runs, with pause, step and run
+ * controls.
+ */
+pub struct DebugWindow {
+    canvas: WindowCanvas,
+    paused: bool,
+    status: String,
+    // first address of the hex view, or `None` to follow I
+    hex_start: Option<u16>,
+    // first address of the hex view when following I
+    followed_start: u16,
+    // closed by the user, until the next stop brings it back
+    hidden: bool,
+}
+
+impl DebugWindow {
+    pub fn new(video_subsystem: &VideoSubsystem) -> Result<DebugWindow, String> {
+        let window = video_subsystem
+            .window("Chip8 debugger", WINDOW_WIDTH, WINDOW_HEIGHT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        Ok(DebugWindow {
+            canvas,
+            paused: false,
+            status: "running".to_string(),
+            hex_start: None,
+            followed_start: 0,
+            hidden: false,
+        })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    fn handle(&mut self, event: &Event) -> Option<Control> {
+        match event {
+            Event::Window {
+                win_event: WindowEvent::Close,
+                ..
+            } => Some(Control::Close),
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match *keycode {
+                Keycode::F5 => Some(Control::Run),
+                Keycode::Space if self.paused => Some(Control::Run),
+                Keycode::Space => Some(Control::Pause),
+                Keycode::F10 | Keycode::N => Some(Control::Step),
+                Keycode::F9 | Keycode::B => Some(Control::ReverseStep),
+                Keycode::Up => self.scroll(-1),
+                Keycode::Down => self.scroll(1),
+                Keycode::PageUp => self.scroll(-HEX_ROWS),
+                Keycode::PageDown => self.scroll(HEX_ROWS),
+                Keycode::Home => {
+                    self.hex_start = None;
+                    None
+                }
+                _ => None,
+            },
+            Event::MouseWheel { y, .. } => self.scroll(-y),
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => BUTTONS
+                .iter()
+                .find(|(_, label, left)| button_rect(label, *left).contains_point((*x, *y)))
+                .map(|(control, _, _)| *control),
+            _ => None,
+        }
+    }
+
+    fn set_hidden(&mut self, hidden: bool) {
+        if hidden {
+            self.canvas.window_mut().hide();
+        } else {
+            self.canvas.window_mut().show();
+        }
+        self.hidden = hidden;
+    }
+
+    fn scroll(&mut self, rows: i32) -> Option<Control> {
+        let start = self.hex_start.unwrap_or(self.followed_start) as i32;
+        let last = 0x1000 - HEX_ROWS * 16;
+        self.hex_start = Some((start + rows * 16).clamp(0, last) as u16);
+        None
+    }
+
+    fn render<D, I, ST, DT>(
+        &mut self,
+        interpreter: &Interpreter<D, I, ST, DT>,
+    ) -> Result<(), String>
+    where
+        I: EventPollable,
+        D: Drawable,
+        ST: TimerActions,
+        DT: TimerActions,
+    {
+        let state = interpreter.state();
+        let memory = interpreter.memory();
+        let canvas = &mut self.canvas;
+        canvas.set_draw_color(BACKGROUND_COLOR);
+        canvas.clear();
+
+        // controls and status
+        for (control, label, left) in BUTTONS {
+            let rect = button_rect(label, left);
+            let active = match control {
+                Control::Run => !self.paused,
+                Control::Pause => self.paused,
+                Control::Step | Control::ReverseStep | Control::Close => false,
+            };
+            canvas.set_draw_color(if active { PC_COLOR } else { LABEL_COLOR });
+            canvas.draw_rect(rect)?;
+            draw_text(
+                canvas,
+                label,
+                (rect.x() + 4, rect.y() + 4),
+                SCALE,
+                TEXT_COLOR,
+            )?;
+        }
+        draw_text(canvas, &self.status, (340, 12), SCALE, TEXT_COLOR)?;
+
+        // disassembly around PC
+        let top = 40;
+        draw_text(canvas, "DISASSEMBLY", (8, top), SCALE, LABEL_COLOR)?;
+        let first = state.pc as i32 - 2 * (DISASSEMBLY_LINES / 2);
+        for line in 0..DISASSEMBLY_LINES {
+            let addr = first + 2 * line;
+            if !(0..memory.value.len() as i32 - 1).contains(&addr) {
+                continue;
+            }
+            let y = top + LINE_HEIGHT * (line + 1);
+            if addr == state.pc as i32 {
+                canvas.set_draw_color(PC_COLOR);
+                canvas.fill_rect(Rect::new(4, y - 2, 270, LINE_HEIGHT as u32))?;
+            }
+            let opcode = memory.read_instruction(addr as u16);
+            let text = format!("{:03X} {:04X} {}", addr, opcode, disassemble(opcode));
+            draw_text(canvas, &text, (8, y), SCALE, TEXT_COLOR)?;
+        }
+
+        // registers, timers and stack
+        let left = 300;
+        draw_text(canvas, "REGISTERS", (left, top), SCALE, LABEL_COLOR)?;
+        for (x, value) in state.v.iter().enumerate() {
+            let column = (x / 8) as i32;
+            let row = (x % 8) as i32;
+            let text = format!("V{:X} {:02X}", x, value);
+            let at = (left + column * 80, top + LINE_HEIGHT * (row + 1));
+            draw_text(canvas, &text, at, SCALE, TEXT_COLOR)?;
+        }
+        let others = [
+            format!("I  {:03X}", state.i),
+            format!("PC {:03X}", state.pc),
+            format!("SP {:X}", state.sp),
+            format!("DT {:02X}", state.dt),
+            format!("ST {:02X}", state.st),
+        ];
+        for (row, text) in others.iter().enumerate() {
+            let at = (left + 160, top + LINE_HEIGHT * (row as i32 + 1));
+            draw_text(canvas, text, at, SCALE, TEXT_COLOR)?;
+        }
+        let stack_top = top + LINE_HEIGHT * 10;
+        draw_text(canvas, "STACK", (left, stack_top), SCALE, LABEL_COLOR)?;
+        for (depth, addr) in state.stack[..state.sp].iter().enumerate() {
+            let at = (
+                left + (depth as i32 / 8) * 60,
+                stack_top + LINE_HEIGHT * (depth as i32 % 8 + 1),
+            );
+            draw_text(canvas, &format!("{:03X}", addr), at, SCALE, TEXT_COLOR)?;
+        }
+
+        // keypad
+        let left = 600;
+        let keypad = interpreter.keypad();
+        draw_text(canvas, "KEYPAD", (left, top), SCALE, LABEL_COLOR)?;
+        for (row, keys) in KEYPAD_LAYOUT.iter().enumerate() {
+            for (column, key) in keys.iter().enumerate() {
+                let rect = Rect::new(
+                    left + column as i32 * 28,
+                    top + LINE_HEIGHT + row as i32 * 28,
+                    24,
+                    24,
+                );
+                canvas.set_draw_color(LABEL_COLOR);
+                if keypad[*key as usize] {
+                    canvas.set_draw_color(KEY_DOWN_COLOR);
+                    canvas.fill_rect(rect)?;
+                } else {
+                    canvas.draw_rect(rect)?;
+                }
+                let label = format!("{:X}", key);
+                draw_text(
+                    canvas,
+                    &label,
+                    (rect.x() + 9, rect.y() + 7),
+                    SCALE,
+                    TEXT_COLOR,
+                )?;
+            }
+        }
+
+        // memory, highlighting the bytes at I
+        self.followed_start = (state.i & !0xF).min(0x1000 - HEX_ROWS as u16 * 16);
+        let start = self.hex_start.unwrap_or(self.followed_start);
+        draw_text(canvas, "MEMORY", (8, HEX_TOP), SCALE, LABEL_COLOR)?;
+        for row in 0..HEX_ROWS {
+            let y = HEX_TOP + LINE_HEIGHT * (row + 1);
+            let addr = start + row as u16 * 16;
+            draw_text(canvas, &format!("{:03X}", addr), (8, y), SCALE, LABEL_COLOR)?;
+            for column in 0..16 {
+                let addr = addr + column;
+                let x = 48 + column as i32 * text_width(3, SCALE) as i32;
+                if (state.i..=state.i.saturating_add(I_HIGHLIGHT_LEN)).contains(&addr) {
+                    canvas.set_draw_color(HIGHLIGHT_COLOR);
+                    canvas.fill_rect(Rect::new(
+                        x - 2,
+                        y - 2,
+                        text_width(2, SCALE) + 2,
+                        LINE_HEIGHT as u32,
+                    ))?;
+                }
+                let byte = format!("{:02X}", memory.value[addr as usize]);
+                draw_text(canvas, &byte, (x, y), SCALE, TEXT_COLOR)?;
+            }
+        }
+
+        canvas.present();
+        Ok(())
+    }
+}
+
+fn button_rect(label: &str, left: i32) -> Rect {
+    Rect::new(
+        left,
+        6,
+        text_width(label.len(), SCALE) + 6,
+        GLYPH_HEIGHT * SCALE + 8,
+    )
+}
+
+/**
+ * Runs the program while showing its state in `window`.
+ */
+pub fn run<D, I, ST, DT>(
+    interpreter: &mut Interpreter<D, I, ST, DT>,
+    window: &mut DebugWindow,
+) -> Result<(), String>
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    loop {
+        let mut step = false;
+        for event in interpreter.input_driver_mut().window_events(window.id()) {
+            match window.handle(&event) {
+                Some(Control::Run) if window.paused => {
+                    window.paused = false;
+                    window.status = "running".to_string();
+                    interpreter.debugger_mut().resume();
+                }
+                Some(Control::Pause) => {
+                    window.paused = true;
+                    window.status = "paused".to_string();
+                }
+                Some(Control::Step) => step = true,
+                // the game keeps running without the debugger
+                Some(Control::Close) => {
+                    window.set_hidden(true);
+                    if window.paused {
+                        window.paused = false;
+                        window.status = "running".to_string();
+                        interpreter.debugger_mut().resume();
+                    }
+                }
+                Some(Control::ReverseStep) => {
+                    window.paused = true;
+                    window.status = if interpreter.reverse_step() {
+                        format!("{} instructions to undo", interpreter.journal_len())
+                    } else {
+                        "reached the start of the journal".to_string()
+                    };
+                }
+                _ => (),
+            }
+        }
+
+        let result = if !window.paused {
+            interpreter.run_frame()
+        } else if step {
+            interpreter.debugger_mut().resume();
+            interpreter.run_instruction()
+        } else {
+            std::thread::sleep(Duration::from_millis(16));
+            interpreter.poll_input().map(|_| None)
+        };
+        match result {
+            Err(_) => return Ok(()),
+            Ok(Some(stop)) => {
+                window.set_hidden(false);
+                window.paused = true;
+                window.status = format!("stopped at {}", stop);
+            }
+            Ok(None) if step => {
+                window.paused = true;
+                window.status = "paused".to_string();
+            }
+            Ok(None) => (),
+        }
+
+        interpreter.present();
+        if !window.hidden {
+            window.render(interpreter)?;
+        }
+    }
+}
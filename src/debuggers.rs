@@ -0,0 +1,410 @@
+use std::fmt;
+
+use crate::expressions::Expr;
+use crate::interpreters::MachineState;
+use crate::memory::Memory;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/**
+ * Watches the inclusive address range `start..=end` for reads, writes or both.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Watchpoint {
+    pub start: u16,
+    pub end: u16,
+    pub on_read: bool,
+    pub on_write: bool,
+}
+
+impl Watchpoint {
+    /**
+     * Parses `addr`, `start-end`, optionally followed by `:r`, `:w` or `:rw` (the default),
+     * e.g. `0x200-0x2ff:w`.
+     */
+    pub fn parse(spec: &str) -> Result<Watchpoint, String> {
+        let (range, access) = spec.split_once(':').unwrap_or((spec, "rw"));
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (parse_addr(start)?, parse_addr(end)?),
+            None => (parse_addr(range)?, parse_addr(range)?),
+        };
+        if start > end {
+            return Err(format!("watchpoint range {} is empty", range));
+        }
+        let (on_read, on_write) = match access {
+            "r" => (true, false),
+            "w" => (false, true),
+            "rw" | "wr" => (true, true),
+            _ => return Err(format!("unknown watchpoint access {:?}", access)),
+        };
+        Ok(Watchpoint {
+            start,
+            end,
+            on_read,
+            on_write,
+        })
+    }
+
+    fn matches(&self, addr: u16, access: Access) -> bool {
+        let wanted = match access {
+            Access::Read => self.on_read,
+            Access::Write => self.on_write,
+        };
+        wanted && (self.start..=self.end).contains(&addr)
+    }
+}
+
+pub fn parse_addr(s: &str) -> Result<u16, String> {
+    let s = s.trim();
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("invalid address {:?}", s))
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct WatchHit {
+    pub pc: u16,
+    pub addr: u16,
+    pub access: Access,
+    pub old: u8,
+    pub new: u8,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.access {
+            Access::Read => write!(
+                f,
+                "watchpoint: {:#05x} read {:#04x} at {:#05x}",
+                self.pc, self.old, self.addr
+            ),
+            Access::Write => write!(
+                f,
+                "watchpoint: {:#05x} wrote {:#04x} -> {:#04x} at {:#05x}",
+                self.pc, self.old, self.new, self.addr
+            ),
+        }
+    }
+}
+
+/**
+ * Stops at `addr` (or at any address if `None`) when `condition` holds, once it was hit
+ * `after` times.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Breakpoint {
+    pub addr: Option<u16>,
+    pub condition: Option<Expr>,
+    pub after: u64,
+    pub hits: u64,
+}
+
+impl Breakpoint {
+    /**
+     * Parses `[addr] [after n] [if condition]`, e.g. `0x2a0 after 3` or
+     * `if V3 == 0x10 && I > 0x300`.
+     */
+    pub fn parse(spec: &str) -> Result<Breakpoint, String> {
+        let (head, condition) = match spec.split_once("if ") {
+            Some((head, condition)) => (head, Some(Expr::parse(condition)?)),
+            None => (spec, None),
+        };
+        let mut words = head.split_whitespace();
+        let mut addr = None;
+        let mut after = 1;
+        while let Some(word) = words.next() {
+            if word == "after" {
+                after = words
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("`after` expects a hit count")?;
+            } else if addr.is_none() {
+                addr = Some(parse_addr(word)?);
+            } else {
+                return Err(format!("unexpected {:?} in breakpoint", word));
+            }
+        }
+        if addr.is_none() && condition.is_none() {
+            return Err("a breakpoint needs an address or a condition".to_string());
+        }
+        Ok(Breakpoint {
+            addr,
+            condition,
+            after,
+            hits: 0,
+        })
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.addr {
+            Some(addr) => write!(f, "{:#05x}", addr)?,
+            None => write!(f, "anywhere")?,
+        }
+        if let Some(condition) = &self.condition {
+            write!(f, " if {}", condition)?;
+        }
+        write!(f, " (hit {}/{})", self.hits, self.after)
+    }
+}
+
+/**
+ * Things a ROM does that the debugger can stop on.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DebugEvent {
+    // DRW turned off a pixel
+    Collision,
+    Clear,
+    // ST was set while the sound was off
+    SoundStart,
+    // about to wait for a key with LD Vx, K
+    KeyWait,
+    // CALL or RET
+    StackDepth,
+    // about to execute bytes that are not an instruction
+    InvalidOpcode,
+}
+
+const EVENT_NAMES: [(DebugEvent, &str); 6] = [
+    (DebugEvent::Collision, "collision"),
+    (DebugEvent::Clear, "cls"),
+    (DebugEvent::SoundStart, "sound"),
+    (DebugEvent::KeyWait, "keywait"),
+    (DebugEvent::StackDepth, "stack"),
+    (DebugEvent::InvalidOpcode, "invalid"),
+];
+
+impl DebugEvent {
+    pub fn parse(name: &str) -> Result<DebugEvent, String> {
+        EVENT_NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(event, _)| *event)
+            .ok_or(format!(
+                "unknown event {:?}, expected one of {}",
+                name,
+                EVENT_NAMES.map(|(_, n)| n).join(", ")
+            ))
+    }
+}
+
+impl fmt::Display for DebugEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (_, name) = EVENT_NAMES.iter().find(|(e, _)| e == self).unwrap();
+        write!(f, "{}", name)
+    }
+}
+
+/**
+ * Why execution stopped.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub enum Stop {
+    Breakpoint(usize),
+    Watchpoint(WatchHit),
+    Event(DebugEvent),
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stop::Breakpoint(idx) => write!(f, "breakpoint {}", idx),
+            Stop::Watchpoint(hit) => write!(f, "{}", hit),
+            Stop::Event(event) => write!(f, "event {}", event),
+        }
+    }
+}
+
+/**
+ * Debugging state the interpreter consults while executing.
+ */
+pub struct Debugger {
+    watchpoints: Vec<Watchpoint>,
+    breakpoints: Vec<Breakpoint>,
+    event_breakpoints: Vec<DebugEvent>,
+    // hits of the instruction being executed
+    hits: Vec<WatchHit>,
+    // events caused by the instruction being executed
+    events: Vec<DebugEvent>,
+    // where the last stop before an instruction happened; stops after one have already
+    // executed it, so there is nothing to skip
+    stopped_at: Option<u16>,
+    // set when resuming so we do not stop again before the instruction we stopped at
+    resuming: Option<u16>,
+    // log hits to stderr as they happen
+    pub trace: bool,
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Debugger::new()
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            watchpoints: Vec::new(),
+            breakpoints: Vec::new(),
+            event_breakpoints: Vec::new(),
+            hits: Vec::new(),
+            events: Vec::new(),
+            stopped_at: None,
+            resuming: None,
+            trace: false,
+        }
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn remove_watchpoint(&mut self, idx: usize) -> Option<Watchpoint> {
+        (idx < self.watchpoints.len()).then(|| self.watchpoints.remove(idx))
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn remove_breakpoint(&mut self, idx: usize) -> Option<Breakpoint> {
+        (idx < self.breakpoints.len()).then(|| self.breakpoints.remove(idx))
+    }
+
+    pub fn add_event_breakpoint(&mut self, event: DebugEvent) {
+        if !self.event_breakpoints.contains(&event) {
+            self.event_breakpoints.push(event);
+        }
+    }
+
+    pub fn event_breakpoints(&self) -> &[DebugEvent] {
+        &self.event_breakpoints
+    }
+
+    pub fn remove_event_breakpoint(&mut self, event: DebugEvent) -> bool {
+        let len = self.event_breakpoints.len();
+        self.event_breakpoints.retain(|e| *e != event);
+        len != self.event_breakpoints.len()
+    }
+
+    /**
+     * Continue after a stop without stopping again before the current instruction.
+     */
+    pub fn resume(&mut self) {
+        self.resuming = self.stopped_at.take();
+    }
+
+    pub(crate) fn check_access(&mut self, pc: u16, addr: u16, access: Access, old: u8, new: u8) {
+        if self.watchpoints.iter().any(|w| w.matches(addr, access)) {
+            let hit = WatchHit {
+                pc,
+                addr,
+                access,
+                old,
+                new,
+            };
+            if self.trace {
+                eprintln!("{}", hit);
+            }
+            self.hits.push(hit);
+        }
+    }
+
+    pub(crate) fn record_event(&mut self, event: DebugEvent) {
+        self.events.push(event);
+    }
+
+    pub(crate) fn begin_step(&mut self) {
+        self.hits.clear();
+        self.events.clear();
+    }
+
+    /**
+     * Checked before the instruction at `state.pc` is executed; `upcoming` is an event that
+     * executing it would cause.
+     */
+    pub(crate) fn check_before(
+        &mut self,
+        state: &MachineState,
+        memory: &Memory,
+        upcoming: Option<DebugEvent>,
+    ) -> Option<Stop> {
+        if self.resuming.take() == Some(state.pc) {
+            return None;
+        }
+        if let Some(event) = upcoming.filter(|e| self.event_breakpoints.contains(e)) {
+            self.stopped_at = Some(state.pc);
+            return Some(Stop::Event(event));
+        }
+        for (idx, breakpoint) in self.breakpoints.iter_mut().enumerate() {
+            let at_addr = breakpoint.addr.is_none_or(|addr| addr == state.pc);
+            if at_addr
+                && breakpoint
+                    .condition
+                    .as_ref()
+                    .is_none_or(|c| c.is_true(state, memory))
+            {
+                breakpoint.hits += 1;
+                if breakpoint.hits >= breakpoint.after {
+                    self.stopped_at = Some(state.pc);
+                    return Some(Stop::Breakpoint(idx));
+                }
+            }
+        }
+        None
+    }
+
+    /**
+     * The first breakpoint at `state.pc` whose condition holds, regardless of hit counts.
+     */
+    pub(crate) fn breakpoint_at(&self, state: &MachineState, memory: &Memory) -> Option<usize> {
+        self.breakpoints.iter().position(|breakpoint| {
+            breakpoint.addr.is_none_or(|addr| addr == state.pc)
+                && breakpoint
+                    .condition
+                    .as_ref()
+                    .is_none_or(|c| c.is_true(state, memory))
+        })
+    }
+
+    /**
+     * Checked after an instruction was executed.
+     */
+    pub(crate) fn check_after(&mut self) -> Option<Stop> {
+        let stop = match self
+            .events
+            .iter()
+            .find(|e| self.event_breakpoints.contains(e))
+        {
+            Some(event) => Some(Stop::Event(*event)),
+            None => self.hits.first().cloned().map(Stop::Watchpoint),
+        };
+        if stop.is_some() {
+            self.stopped_at = None;
+        }
+        stop
+    }
+
+    /**
+     * The watchpoint hits of the last executed instruction.
+     */
+    pub fn hits(&self) -> &[WatchHit] {
+        &self.hits
+    }
+}
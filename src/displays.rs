@@ -1,20 +1,35 @@
 extern crate sdl2;
 
-use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use sdl2::render::WindowCanvas;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
+use sdl2::video::{FullscreenType, WindowContext};
 use sdl2::VideoSubsystem;
 
-const DOT_SIZE_IN_PXS: u32 = 20;
+use crate::configs::Settings;
+use crate::effects::Effects;
+use crate::filters::{mix, Filter, Image};
+use crate::glyphs::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::input_driver::Pointer;
+use crate::onscreen_keypads::{KeypadPlacement, OnScreenKeypad};
+use crate::palettes::Palette;
+use crate::viewports::Viewport;
 
-const GRID_WIDTH: usize = 64;
-const GRID_HEIGHT: usize = 32;
+pub(crate) const GRID_WIDTH: usize = 64;
+pub(crate) const GRID_HEIGHT: usize = 32;
 
-const SCREEN_WIDTH: u32 = GRID_WIDTH as u32 * DOT_SIZE_IN_PXS;
-const SCREEN_HEIGHT: u32 = GRID_HEIGHT as u32 * DOT_SIZE_IN_PXS;
+const GRID_SIZE: (u32, u32) = (GRID_WIDTH as u32, GRID_HEIGHT as u32);
+// grid lines would hide smaller pixels
+const MIN_GRID_SCALE: f64 = 4.0;
 
-const BACKGROUND_COLOR: Color = Color::RGB(30, 30, 30);
+const LETTERBOX_COLOR: Color = Color::BLACK;
+const KEY_COLOR: Color = Color::RGB(0x40, 0x40, 0x40);
+const KEY_LABEL_COLOR: Color = Color::WHITE;
+const TESTED_KEY_COLOR: Color = Color::RGB(0xff, 0xb0, 0x00);
+// how opaque the keypad is when drawn over the game
+const OVERLAY_ALPHA: u8 = 0x90;
 
+#[derive(Clone)]
 pub struct GamePixels {
     pub value: [[u8; GRID_WIDTH]; GRID_HEIGHT],
 }
@@ -41,6 +56,26 @@ impl GamePixels {
 
         total_collision
     }
+
+    /**
+     * The pixels as text, two rows per line drawn with half blocks.
+     */
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for rows in self.value.chunks(2) {
+            let line: String = (0..GRID_WIDTH)
+                .map(|x| match (rows[0][x], rows[1][x]) {
+                    (0, 0) => ' ',
+                    (_, 0) => '▀',
+                    (0, _) => '▄',
+                    _ => '█',
+                })
+                .collect();
+            text += line.trim_end();
+            text.push('\n');
+        }
+        text
+    }
 }
 
 pub trait Drawable {
@@ -52,72 +87,445 @@ pub trait Drawable {
      */
     fn draw_at(&mut self, bytes: &[u8], start_coord: (usize, usize)) -> bool;
     fn clear(&mut self);
+    fn pixels(&self) -> &GamePixels;
+    /*
+     * Replace the displayed pixels, e.g. when undoing a CLS.
+     */
+    fn restore(&mut self, pixels: GamePixels);
+    /*
+     * Switch to the next built-in palette, for displays that have one.
+     */
+    fn cycle_palette(&mut self) {}
+    /*
+     * Show the pixels drawn so far. Called once per frame, displays only redraw if the
+     * pixels changed since.
+     */
+    fn present(&mut self) {}
+    /*
+     * An emulated frame ended. Anything that changes with time, like fading pixels, only
+     * advances here, however often the picture is presented.
+     */
+    fn end_frame(&mut self) {}
+    /*
+     * Switch to the next scaling filter, for displays that have them.
+     */
+    fn cycle_filter(&mut self) {}
+    /*
+     * The picture as last shown, for displays that can take screenshots.
+     */
+    fn screenshot(&self) -> Option<Image> {
+        None
+    }
+    /*
+     * The display effects, for displays that support them.
+     */
+    fn effects_mut(&mut self) -> Option<&mut Effects> {
+        None
+    }
+    /*
+     * How the pixels are laid out in the window, for displays that have one.
+     */
+    fn viewport_mut(&mut self) -> Option<&mut Viewport> {
+        None
+    }
+    /*
+     * The CHIP-8 key under `pointer`, for displays with an on-screen keypad.
+     */
+    fn touched_key(&self, _pointer: Pointer) -> Option<u8> {
+        None
+    }
+    /*
+     * Show which keys are held, for displays with an on-screen keypad.
+     */
+    fn show_pressed(&mut self, _keypad: [bool; 16]) {}
+    /*
+     * Highlight a key the ROM tested with SKP or SKNP, for displays with an on-screen
+     * keypad.
+     */
+    fn key_tested(&mut self, _key: u8) {}
+    /*
+     * Fill in how the display is currently set up, for saving it to the config file.
+     */
+    fn record_settings(&self, _settings: &mut Settings) {}
+    /*
+     * Show the name of the game, for displays with a title.
+     */
+    fn set_title(&mut self, _title: &str) {}
 }
 
-impl Drawable for Display {
+impl Drawable for Display<'_> {
     fn draw_at(&mut self, bytes: &[u8], start_coord: (usize, usize)) -> bool {
         // convert each byte to [u8;8] - these should be "stacked"
         // update `pixels` with these bits starting at start_coord -- track if there is collision
         let collided = self.pixels.fill_in_bytes(bytes, start_coord);
-        // display pixels
-        self.draw_pixels().unwrap();
+        self.dirty = true;
 
         collided
     }
 
     fn clear(&mut self) {
         self.pixels = GamePixels::new();
-        self.draw_background()
+        self.dirty = true;
+    }
+
+    fn pixels(&self) -> &GamePixels {
+        &self.pixels
+    }
+
+    fn restore(&mut self, pixels: GamePixels) {
+        self.pixels = pixels;
+        self.dirty = true;
+    }
+
+    fn cycle_palette(&mut self) {
+        self.palette = self.palette.next();
+        self.dirty = true;
+    }
+
+    fn cycle_filter(&mut self) {
+        self.filter = self.filter.next();
+        self.dirty = true;
+    }
+
+    fn screenshot(&self) -> Option<Image> {
+        Some(self.frame.clone())
+    }
+
+    fn present(&mut self) {
+        if let Err(e) = self.render() {
+            eprintln!("couldn't draw the frame: {}", e);
+        }
+        self.canv.present();
+    }
+
+    fn end_frame(&mut self) {
+        if self.effects.is_active() {
+            self.effects.apply(&self.pixels);
+        }
+        if let Some(keypad) = &mut self.keypad {
+            keypad.end_frame();
+        }
+    }
+
+    fn effects_mut(&mut self) -> Option<&mut Effects> {
+        Some(&mut self.effects)
+    }
+
+    fn viewport_mut(&mut self) -> Option<&mut Viewport> {
+        Some(&mut self.viewport)
+    }
+
+    fn touched_key(&self, pointer: Pointer) -> Option<u8> {
+        let keypad = self.keypad.as_ref()?;
+        // window coordinates differ from the drawable's pixels on high DPI screens
+        let (width, height) = self.canv.window().size();
+        let output = self.canv.output_size().ok()?;
+        let (x, y) = match pointer {
+            Pointer::Mouse(x, y) => (x as f64 / width as f64, y as f64 / height as f64),
+            Pointer::Finger(x, y) => (x as f64, y as f64),
+        };
+        let point = ((x * output.0 as f64) as i32, (y * output.1 as f64) as i32);
+        keypad.key_at(output, point)
+    }
+
+    fn show_pressed(&mut self, keypad: [bool; 16]) {
+        if let Some(onscreen) = &mut self.keypad {
+            onscreen.show_pressed(keypad);
+        }
+    }
+
+    fn key_tested(&mut self, key: u8) {
+        if let Some(keypad) = &mut self.keypad {
+            keypad.test(key);
+        }
+    }
+
+    fn set_title(&mut self, title: &str) {
+        let _ = self
+            .canv
+            .window_mut()
+            .set_title(&format!("{} - Chip8", title));
+    }
+
+    fn record_settings(&self, settings: &mut Settings) {
+        settings.palette = Some(self.palette.name.clone());
+        settings.filter = Some(self.filter.name().to_string());
+        settings.scaling = Some(self.viewport.scaling_name().to_string());
+        settings.scale = Some(self.viewport.window_scale);
+        settings.rotate = Some(self.viewport.rotation);
+        settings.flip = Some(self.viewport.flip_spec().to_string());
+        settings.grid = Some(self.viewport.grid);
+        settings.fullscreen = Some(self.viewport.fullscreen);
+        settings.phosphor = Some(if self.effects.phosphor {
+            self.effects.half_life_ms()
+        } else {
+            0.0
+        });
+        settings.blend = Some(if self.effects.blend {
+            self.effects.blend_spec()
+        } else {
+            "none".to_string()
+        });
+        settings.keypad = Some(
+            self.keypad
+                .as_ref()
+                .map_or("none", |keypad| keypad.placement_name())
+                .to_string(),
+        );
     }
 }
 
-pub struct Display {
+pub struct Display<'a> {
     pixels: GamePixels,
     canv: WindowCanvas,
+    textures: &'a TextureCreator<WindowContext>,
+    // the filtered frame, scaled up to the window when copied
+    texture: Texture<'a>,
+    // the filtered frame last uploaded to the texture
+    frame: Image,
+    filter: Filter,
+    // whether the pixels changed since they were last uploaded to the texture
+    dirty: bool,
+    palette: Palette,
+    effects: Effects,
+    viewport: Viewport,
+    keypad: Option<OnScreenKeypad>,
 }
 
-impl Display {
-    pub fn new(video_subsystem: VideoSubsystem) -> Result<Display, String> {
-        let window = video_subsystem
-            .window("Chip8", SCREEN_WIDTH, SCREEN_HEIGHT)
-            .position_centered()
-            .opengl()
-            .build()
-            .map_err(|e| e.to_string())?;
+/**
+ * Opens the game's window, sized for the viewport and the keypad panel. With `vsync`
+ * presenting a frame waits for the monitor's vertical blank, which avoids tearing and
+ * paces the game to the monitor's refresh rate.
+ */
+pub fn open_window(
+    video_subsystem: VideoSubsystem,
+    viewport: &Viewport,
+    keypad: Option<&OnScreenKeypad>,
+    vsync: bool,
+) -> Result<WindowCanvas, String> {
+    let (width, height) = viewport.oriented(GRID_SIZE);
+    let (width, height) = (
+        width * viewport.window_scale,
+        height * viewport.window_scale,
+    );
+    // make room for a keypad panel half as wide as the window is high
+    let width = match keypad.map(|keypad| keypad.placement) {
+        Some(KeypadPlacement::Side) => width + height / 2,
+        _ => width,
+    };
+    let window = video_subsystem
+        .window("Chip8", width, height)
+        .position_centered()
+        .resizable()
+        .opengl()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut canvas = window.into_canvas();
+    if vsync {
+        canvas = canvas.present_vsync();
+    }
+    canvas.build().map_err(|e| e.to_string())
+}
 
-        let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+impl<'a> Display<'a> {
+    /**
+     * Draws into the canvas of `open_window`, with `textures` made from that canvas.
+     */
+    pub fn new(
+        mut canvas: WindowCanvas,
+        textures: &'a TextureCreator<WindowContext>,
+        palette: Palette,
+        effects: Effects,
+        viewport: Viewport,
+        filter: Filter,
+        keypad: Option<OnScreenKeypad>,
+    ) -> Result<Display<'a>, String> {
+        let texture = textures
+            .create_texture_streaming(
+                PixelFormatEnum::RGB24,
+                GRID_WIDTH as u32,
+                GRID_HEIGHT as u32,
+            )
+            .map_err(|e| e.to_string())?;
 
-        canvas.set_draw_color(BACKGROUND_COLOR);
+        canvas.set_draw_color(palette.color(0));
         canvas.clear();
         canvas.present();
 
         Ok(Display {
             canv: canvas,
             pixels: GamePixels::new(),
+            textures,
+            texture,
+            frame: Image::new(GRID_WIDTH, GRID_HEIGHT),
+            filter,
+            dirty: true,
+            palette,
+            effects,
+            viewport,
+            keypad,
         })
     }
 
-    pub fn draw_background(&mut self) {
-        self.canv.set_draw_color(BACKGROUND_COLOR);
-        self.canv.clear()
+    fn render(&mut self) -> Result<(), String> {
+        // the effects change the picture even when the pixels don't
+        if self.dirty || self.effects.is_active() {
+            let image = self.frame_image();
+            let frame = self.filter.apply(&image);
+            self.upload(&frame)?;
+            self.frame = frame;
+            self.dirty = false;
+        }
+        // copied every frame as the window contents are lost e.g. when it is uncovered
+        self.draw_picture()?;
+        self.draw_keypad()
     }
-    fn draw_pixels(&mut self) -> Result<(), String> {
-        for (y, row) in self.pixels.value.iter().enumerate() {
-            for (x, bit) in row.iter().enumerate() {
-                let x = x as u32 * DOT_SIZE_IN_PXS;
-                let y = y as u32 * DOT_SIZE_IN_PXS;
-                let rect = Rect::new(x as i32, y as i32, DOT_SIZE_IN_PXS, DOT_SIZE_IN_PXS);
-                self.canv.set_draw_color(if *bit == 1 {
-                    Color::GREEN
-                } else {
-                    BACKGROUND_COLOR
-                });
 
-                self.canv.fill_rect(rect).unwrap();
+    fn draw_picture(&mut self) -> Result<(), String> {
+        let fullscreen = if self.viewport.fullscreen {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Off
+        };
+        if self.canv.window().fullscreen_state() != fullscreen {
+            self.canv.window_mut().set_fullscreen(fullscreen)?;
+        }
+
+        let output = self.canv.output_size()?;
+        let panel = self
+            .keypad
+            .as_ref()
+            .map_or(0, |keypad| keypad.panel_width(output));
+        let window = (output.0 - panel, output.1);
+        self.canv.set_draw_color(LETTERBOX_COLOR);
+        self.canv.clear();
+        self.canv.copy_ex(
+            &self.texture,
+            None,
+            self.viewport.copy_target(window, GRID_SIZE),
+            self.viewport.rotation as f64,
+            None,
+            self.viewport.flip_horizontal,
+            self.viewport.flip_vertical,
+        )?;
+
+        let scale = self.viewport.scale(window, GRID_SIZE);
+        if self.viewport.grid && scale >= MIN_GRID_SCALE {
+            let picture = self.viewport.picture(window, GRID_SIZE);
+            let (columns, rows) = self.viewport.oriented(GRID_SIZE);
+            let offset = |idx: u32| (idx as f64 * scale).round() as i32;
+            self.canv.set_draw_color(self.palette.color(0));
+            for column in 1..columns {
+                let x = picture.left() + offset(column);
+                self.canv.draw_line(
+                    Point::new(x, picture.top()),
+                    Point::new(x, picture.bottom()),
+                )?;
+            }
+            for row in 1..rows {
+                let y = picture.top() + offset(row);
+                self.canv.draw_line(
+                    Point::new(picture.left(), y),
+                    Point::new(picture.right(), y),
+                )?;
             }
         }
+        Ok(())
+    }
 
-        self.canv.present();
+    fn draw_keypad(&mut self) -> Result<(), String> {
+        let Some(keypad) = &self.keypad else {
+            return Ok(());
+        };
+        let output = self.canv.output_size()?;
+        let alpha = match keypad.placement {
+            KeypadPlacement::Side => 0xff,
+            KeypadPlacement::Overlay => OVERLAY_ALPHA,
+        };
+        let with_alpha = |color: Color| Color::RGBA(color.r, color.g, color.b, alpha);
+        self.canv.set_blend_mode(BlendMode::Blend);
+        for (key, rect) in keypad.key_rects(output) {
+            let (fill, label) = if keypad.is_pressed(key) {
+                (self.palette.color(1), self.palette.color(0))
+            } else {
+                (KEY_COLOR, KEY_LABEL_COLOR)
+            };
+            self.canv.set_draw_color(with_alpha(fill));
+            self.canv.fill_rect(rect)?;
+            if keypad.is_tested(key) {
+                let border = (rect.width() / 16).max(1);
+                self.canv.set_draw_color(TESTED_KEY_COLOR);
+                for inset in 0..border {
+                    self.canv.draw_rect(Rect::new(
+                        rect.left() + inset as i32,
+                        rect.top() + inset as i32,
+                        rect.width().saturating_sub(2 * inset).max(1),
+                        rect.height().saturating_sub(2 * inset).max(1),
+                    ))?;
+                }
+            }
+            let scale = (rect.height() / (3 * GLYPH_HEIGHT)).max(1);
+            let label_at = (
+                rect.center().x() - text_width(1, scale) as i32 / 2,
+                rect.center().y() - (GLYPH_HEIGHT * scale) as i32 / 2,
+            );
+            draw_text(
+                &mut self.canv,
+                &format!("{:X}", key),
+                label_at,
+                scale,
+                with_alpha(label),
+            )?;
+        }
+        self.canv.set_blend_mode(BlendMode::None);
         Ok(())
     }
+
+    /**
+     * The current frame in colour, before filtering.
+     */
+    fn frame_image(&mut self) -> Image {
+        let mut image = Image::new(GRID_WIDTH, GRID_HEIGHT);
+        if self.effects.is_active() {
+            let background = self.palette.color(0);
+            let shades = self.effects.shades(&self.pixels);
+            for (color, (value, brightness)) in image.pixels.iter_mut().zip(shades.iter().flatten())
+            {
+                *color = mix(background, self.palette.color(*value), *brightness);
+            }
+        } else {
+            for (color, bit) in image
+                .pixels
+                .iter_mut()
+                .zip(self.pixels.value.iter().flatten())
+            {
+                *color = self.palette.color(*bit);
+            }
+        }
+        image
+    }
+
+    fn upload(&mut self, image: &Image) -> Result<(), String> {
+        let query = self.texture.query();
+        if (query.width as usize, query.height as usize) != (image.width, image.height) {
+            self.texture = self
+                .textures
+                .create_texture_streaming(
+                    PixelFormatEnum::RGB24,
+                    image.width as u32,
+                    image.height as u32,
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        self.texture
+            .with_lock(None, |buffer: &mut [u8], pitch: usize| {
+                for (y, row) in image.pixels.chunks(image.width).enumerate() {
+                    for (x, color) in row.iter().enumerate() {
+                        let offset = y * pitch + x * 3;
+                        buffer[offset..offset + 3].copy_from_slice(&[color.r, color.g, color.b]);
+                    }
+                }
+            })
+    }
 }
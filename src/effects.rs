@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+
+use crate::displays::{GamePixels, GRID_HEIGHT, GRID_WIDTH};
+
+// the effects advance once per emulated frame
+const FRAME_MS: f32 = 1000.0 / 60.0;
+
+const DEFAULT_HALF_LIFE_MS: f32 = 50.0;
+const DEFAULT_BLEND_FRAMES: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendMode {
+    // a pixel is lit if it was lit in any of the frames
+    Or,
+    // a pixel is as bright as the share of frames it was lit in
+    Average,
+}
+
+/**
+ * For every pixel the pixel value whose colour to show and how bright to show it, from 0
+ * (the background colour) to 1.
+ */
+pub type Shades = [[(u8, f32); GRID_WIDTH]; GRID_HEIGHT];
+
+/**
+ * Display effects against the flicker of sprites being erased and redrawn with XOR:
+ * phosphor persistence, where pixels that turn off fade out, and blending the last few
+ * frames together.
+ */
+pub struct Effects {
+    pub phosphor: bool,
+    half_life_ms: f32,
+    pub blend: bool,
+    blend_mode: BlendMode,
+    blend_frames: usize,
+    // the most recent frame last
+    history: VecDeque<GamePixels>,
+    glow: Shades,
+}
+
+impl Effects {
+    pub fn new() -> Effects {
+        Effects {
+            phosphor: false,
+            half_life_ms: DEFAULT_HALF_LIFE_MS,
+            blend: false,
+            blend_mode: BlendMode::Or,
+            blend_frames: DEFAULT_BLEND_FRAMES,
+            history: VecDeque::new(),
+            glow: [[(0, 0.0); GRID_WIDTH]; GRID_HEIGHT],
+        }
+    }
+
+    /**
+     * Turns on phosphor persistence, with pixels losing half their brightness every
+     * `half_life_ms` after turning off.
+     */
+    pub fn set_phosphor(&mut self, half_life_ms: f32) {
+        self.phosphor = true;
+        self.half_life_ms = half_life_ms;
+    }
+
+    /**
+     * Parses a half-life in milliseconds for `set_phosphor`.
+     */
+    pub fn parse_half_life(spec: &str) -> Result<f32, String> {
+        match spec.parse::<f32>() {
+            Ok(ms) if ms > 0.0 => Ok(ms),
+            _ => Err(format!(
+                "invalid half-life {:?}, expected milliseconds",
+                spec
+            )),
+        }
+    }
+
+    /**
+     * Turns on blending of the last `frames` frames.
+     */
+    pub fn set_blend(&mut self, mode: BlendMode, frames: usize) {
+        self.blend = true;
+        self.blend_mode = mode;
+        self.blend_frames = frames;
+    }
+
+    /**
+     * Parses `or` or `average` with an optional frame count, e.g. `average:3`.
+     */
+    pub fn parse_blend(spec: &str) -> Result<(BlendMode, usize), String> {
+        let (mode, frames) = spec.split_once(':').unwrap_or((spec, ""));
+        let mode = match mode {
+            "or" => BlendMode::Or,
+            "average" => BlendMode::Average,
+            _ => {
+                return Err(format!(
+                    "unknown blend mode {:?}, expected or or average",
+                    mode
+                ))
+            }
+        };
+        let frames = match frames {
+            "" => DEFAULT_BLEND_FRAMES,
+            n => match n.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(format!("invalid blend frame count {:?}", n)),
+            },
+        };
+        Ok((mode, frames))
+    }
+
+    pub fn half_life_ms(&self) -> f32 {
+        self.half_life_ms
+    }
+
+    /**
+     * The blend mode and frame count in the format of `parse_blend`.
+     */
+    pub fn blend_spec(&self) -> String {
+        let mode = match self.blend_mode {
+            BlendMode::Or => "or",
+            BlendMode::Average => "average",
+        };
+        format!("{}:{}", mode, self.blend_frames)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.phosphor || self.blend
+    }
+
+    /**
+     * Advances the effects by one emulated frame ending with `pixels`.
+     */
+    pub fn apply(&mut self, pixels: &GamePixels) -> Shades {
+        self.history.push_back(pixels.clone());
+        while self.history.len() > self.blend_frames {
+            self.history.pop_front();
+        }
+
+        let frames = if self.blend { self.history.len() } else { 1 };
+        let decay = if self.phosphor {
+            0.5f32.powf(FRAME_MS / self.half_life_ms)
+        } else {
+            0.0
+        };
+        for (y, row) in self.glow.iter_mut().enumerate() {
+            for (x, (value, brightness)) in row.iter_mut().enumerate() {
+                let mut lit = 0;
+                let mut latest = 0;
+                for frame in self.history.iter().rev().take(frames) {
+                    let pixel = frame.value[y][x];
+                    if pixel != 0 {
+                        lit += 1;
+                        if latest == 0 {
+                            latest = pixel;
+                        }
+                    }
+                }
+                let blended = match self.blend_mode {
+                    _ if lit == 0 => 0.0,
+                    BlendMode::Or => 1.0,
+                    BlendMode::Average => lit as f32 / frames as f32,
+                };
+                let faded = *brightness * decay;
+                if blended > 0.0 && blended >= faded {
+                    *value = latest;
+                    *brightness = blended;
+                } else {
+                    *brightness = faded;
+                }
+            }
+        }
+        self.glow
+    }
+
+    /**
+     * What to show for `pixels` without advancing the effects, however often the picture
+     * is redrawn. Pixels turned on since the last frame ended, e.g. while stepping in a
+     * debugger, are shown at full brightness.
+     */
+    pub fn shades(&self, pixels: &GamePixels) -> Shades {
+        let mut shades = self.glow;
+        let last = self.history.back();
+        for (y, row) in shades.iter_mut().enumerate() {
+            for (x, shade) in row.iter_mut().enumerate() {
+                let pixel = pixels.value[y][x];
+                if pixel != 0 && last.is_none_or(|frame| frame.value[y][x] != pixel) {
+                    *shade = (pixel, 1.0);
+                }
+            }
+        }
+        shades
+    }
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Effects::new()
+    }
+}
+
+#[cfg(test)]
+#[path = "./effects_test.rs"]
+mod effects_test;
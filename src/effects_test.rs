@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use crate::displays::GamePixels;
+    use crate::effects::{BlendMode, Effects};
+
+    fn lit_at_origin() -> GamePixels {
+        let mut pixels = GamePixels::new();
+        pixels.value[0][0] = 1;
+        pixels
+    }
+
+    #[test]
+    fn test_phosphor_halves_brightness_every_half_life() {
+        let mut effects = Effects::new();
+        effects.set_phosphor(1000.0 / 60.0);
+        assert_eq!(effects.apply(&lit_at_origin())[0][0], (1, 1.0));
+        assert_eq!(effects.apply(&GamePixels::new())[0][0], (1, 0.5));
+        assert_eq!(effects.apply(&GamePixels::new())[0][0], (1, 0.25));
+        assert_eq!(effects.apply(&lit_at_origin())[0][0], (1, 1.0));
+    }
+
+    #[test]
+    fn test_shades_do_not_fade_between_frames() {
+        let mut effects = Effects::new();
+        effects.set_phosphor(1000.0 / 60.0);
+        effects.apply(&lit_at_origin());
+        effects.apply(&GamePixels::new());
+        for _ in 0..3 {
+            assert_eq!(effects.shades(&GamePixels::new())[0][0], (1, 0.5));
+        }
+
+        // drawn since the frame ended
+        let mut pixels = GamePixels::new();
+        pixels.value[1][2] = 1;
+        assert_eq!(effects.shades(&pixels)[1][2], (1, 1.0));
+        assert_eq!(effects.shades(&pixels)[0][0], (1, 0.5));
+    }
+
+    #[test]
+    fn test_without_effects_pixels_turn_off_immediately() {
+        let mut effects = Effects::new();
+        effects.apply(&lit_at_origin());
+        assert_eq!(effects.apply(&GamePixels::new())[0][0].1, 0.0);
+    }
+
+    #[test]
+    fn test_blend_or_keeps_pixels_lit_for_n_frames() {
+        let mut effects = Effects::new();
+        effects.set_blend(BlendMode::Or, 2);
+        effects.apply(&lit_at_origin());
+        assert_eq!(effects.apply(&GamePixels::new())[0][0].1, 1.0);
+        assert_eq!(effects.apply(&GamePixels::new())[0][0].1, 0.0);
+    }
+
+    #[test]
+    fn test_blend_average() {
+        let mut effects = Effects::new();
+        effects.set_blend(BlendMode::Average, 4);
+        effects.apply(&lit_at_origin());
+        effects.apply(&GamePixels::new());
+        effects.apply(&lit_at_origin());
+        assert_eq!(effects.apply(&GamePixels::new())[0][0].1, 0.5);
+    }
+
+    #[test]
+    fn test_parse_blend() {
+        assert_eq!(Effects::parse_blend("or"), Ok((BlendMode::Or, 2)));
+        assert_eq!(
+            Effects::parse_blend("average:3"),
+            Ok((BlendMode::Average, 3))
+        );
+        assert!(Effects::parse_blend("average:0").is_err());
+        assert!(Effects::parse_blend("max").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt;
+
+use crate::interpreters::MachineState;
+use crate::memory::Memory;
+
+/**
+ * Small expressions over the machine state used by conditional breakpoints, e.g.
+ * `V3 == 0x10 && I > 0x300` or `[I + 2] != 0`.
+ *
+ * Operands are numbers (decimal or `0x` hex), the registers `V0`-`VF`, `I`, `PC`, `SP`,
+ * `DT` and `ST`, and memory bytes `[expr]`. Operators in order of increasing precedence are
+ * `||`, `&&`, comparisons, `|`, `^`, `&`, `+`/`-` and the unary `!`/`-`.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    Number(i64),
+    V(usize),
+    I,
+    PC,
+    SP,
+    DT,
+    ST,
+    Memory(Box<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Sub,
+}
+
+impl BinaryOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+        }
+    }
+}
+
+// binary operators grouped by precedence, lowest first
+const PRECEDENCE: [&[BinaryOp]; 7] = [
+    &[BinaryOp::Or],
+    &[BinaryOp::And],
+    &[
+        BinaryOp::Eq,
+        BinaryOp::Ne,
+        BinaryOp::Le,
+        BinaryOp::Ge,
+        BinaryOp::Lt,
+        BinaryOp::Gt,
+    ],
+    &[BinaryOp::BitOr],
+    &[BinaryOp::BitXor],
+    &[BinaryOp::BitAnd],
+    &[BinaryOp::Add, BinaryOp::Sub],
+];
+
+impl Expr {
+    pub fn parse(input: &str) -> Result<Expr, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.binary(0)?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {:?} in {:?}", token, input)),
+        }
+    }
+
+    pub fn eval(&self, state: &MachineState, memory: &Memory) -> i64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::V(x) => state.v[*x] as i64,
+            Expr::I => state.i as i64,
+            Expr::PC => state.pc as i64,
+            Expr::SP => state.sp as i64,
+            Expr::DT => state.dt as i64,
+            Expr::ST => state.st as i64,
+            Expr::Memory(addr) => {
+                let addr = addr.eval(state, memory);
+                usize::try_from(addr)
+                    .ok()
+                    .and_then(|addr| memory.value.get(addr))
+                    .map_or(0, |byte| *byte as i64)
+            }
+            Expr::Not(e) => (e.eval(state, memory) == 0) as i64,
+            Expr::Neg(e) => e.eval(state, memory).wrapping_neg(),
+            Expr::Binary(lhs, op, rhs) => {
+                let lhs = lhs.eval(state, memory);
+                // short circuit so `[..]` on the right is not evaluated needlessly
+                match op {
+                    BinaryOp::Or if lhs != 0 => return 1,
+                    BinaryOp::And if lhs == 0 => return 0,
+                    _ => (),
+                }
+                let rhs = rhs.eval(state, memory);
+                match op {
+                    BinaryOp::Or | BinaryOp::And => (rhs != 0) as i64,
+                    BinaryOp::Eq => (lhs == rhs) as i64,
+                    BinaryOp::Ne => (lhs != rhs) as i64,
+                    BinaryOp::Lt => (lhs < rhs) as i64,
+                    BinaryOp::Le => (lhs <= rhs) as i64,
+                    BinaryOp::Gt => (lhs > rhs) as i64,
+                    BinaryOp::Ge => (lhs >= rhs) as i64,
+                    BinaryOp::BitOr => lhs | rhs,
+                    BinaryOp::BitXor => lhs ^ rhs,
+                    BinaryOp::BitAnd => lhs & rhs,
+                    BinaryOp::Add => lhs.wrapping_add(rhs),
+                    BinaryOp::Sub => lhs.wrapping_sub(rhs),
+                }
+            }
+        }
+    }
+
+    pub fn is_true(&self, state: &MachineState, memory: &Memory) -> bool {
+        self.eval(state, memory) != 0
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{:#x}", n),
+            Expr::V(x) => write!(f, "V{:X}", x),
+            Expr::I => write!(f, "I"),
+            Expr::PC => write!(f, "PC"),
+            Expr::SP => write!(f, "SP"),
+            Expr::DT => write!(f, "DT"),
+            Expr::ST => write!(f, "ST"),
+            Expr::Memory(addr) => write!(f, "[{}]", addr),
+            Expr::Not(e) => write!(f, "!{}", e),
+            Expr::Neg(e) => write!(f, "-{}", e),
+            Expr::Binary(lhs, op, rhs) => write!(f, "({} {} {})", lhs, op.symbol(), rhs),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 18] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "|", "^", "&", "+", "-", "!", "(", ")", "[", "]",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if rest.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            let token = if word.starts_with(|c: char| c.is_ascii_digit()) {
+                let number = match word.strip_prefix("0x").or(word.strip_prefix("0X")) {
+                    Some(hex) => i64::from_str_radix(hex, 16),
+                    None => word.parse(),
+                };
+                Token::Number(number.map_err(|_| format!("invalid number {:?}", word))?)
+            } else {
+                Token::Ident(word.to_ascii_uppercase())
+            };
+            tokens.push(token);
+            rest = &rest[end..];
+        } else {
+            return Err(format!("unexpected character in {:?}", rest));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        'operators: loop {
+            for op in PRECEDENCE[level] {
+                if self.eat(op.symbol()) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(Box::new(lhs), *op, Box::new(rhs));
+                    continue 'operators;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            Ok(Expr::Not(Box::new(self.unary()?)))
+        } else if self.eat("-") {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        if self.eat("(") {
+            let expr = self.binary(0)?;
+            return if self.eat(")") {
+                Ok(expr)
+            } else {
+                Err("expected `)`".to_string())
+            };
+        }
+        if self.eat("[") {
+            let expr = self.binary(0)?;
+            return if self.eat("]") {
+                Ok(Expr::Memory(Box::new(expr)))
+            } else {
+                Err("expected `]`".to_string())
+            };
+        }
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "I" => Ok(Expr::I),
+                "PC" => Ok(Expr::PC),
+                "SP" => Ok(Expr::SP),
+                "DT" => Ok(Expr::DT),
+                "ST" => Ok(Expr::ST),
+                _ => name
+                    .strip_prefix('V')
+                    .filter(|x| x.len() == 1)
+                    .and_then(|x| usize::from_str_radix(x, 16).ok())
+                    .map(Expr::V)
+                    .ok_or(format!("unknown register {:?}", name)),
+            },
+            Some(Token::Op(op)) => Err(format!("unexpected {:?}", op)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./expressions_test.rs"]
+mod expressions_test;
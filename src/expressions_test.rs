@@ -0,0 +1,51 @@
+mod tests {
+    use crate::{expressions::Expr, interpreters::MachineState, memory::Memory};
+
+    fn make_state() -> MachineState {
+        let mut state = MachineState {
+            v: [0; 16],
+            i: 0x320,
+            pc: 0x200,
+            sp: 1,
+            stack: [0; 16],
+            dt: 3,
+            st: 0,
+        };
+        state.v[3] = 0x10;
+        state
+    }
+    fn eval(input: &str) -> i64 {
+        let mut memory = Memory::empty();
+        memory.value[0x322] = 7;
+        Expr::parse(input).unwrap().eval(&make_state(), &memory)
+    }
+    #[test]
+    fn test_conditions() {
+        assert_eq!(eval("V3 == 0x10 && I > 0x300"), 1);
+        assert_eq!(eval("v3 != 16 || DT < 3"), 0);
+        assert_eq!(eval("!(SP >= 1)"), 0);
+    }
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 & 3"), 3);
+        assert_eq!(eval("1 | 2 == 3"), 1);
+        assert_eq!(eval("PC - 0x100 - 0x100"), 0);
+    }
+    #[test]
+    fn test_overflow_wraps() {
+        assert_eq!(eval("-(0 - 0x7fffffffffffffff - 1)"), i64::MIN);
+        assert_eq!(eval("0x7fffffffffffffff + 1"), i64::MIN);
+    }
+    #[test]
+    fn test_memory_operand() {
+        assert_eq!(eval("[I + 2]"), 7);
+        assert_eq!(eval("[0x10000]"), 0);
+    }
+    #[test]
+    fn test_parse_errors() {
+        assert!(Expr::parse("V3 ==").is_err());
+        assert!(Expr::parse("VG == 1").is_err());
+        assert!(Expr::parse("(V0").is_err());
+        assert!(Expr::parse("V0 $ 1").is_err());
+    }
+}
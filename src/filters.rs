@@ -0,0 +1,313 @@
+use sdl2::pixels::Color;
+
+/**
+ * An RGB picture, row by row from the top left.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![Color::BLACK; width * height],
+        }
+    }
+
+    // coordinates outside the picture are clamped to its edges
+    fn at(&self, x: isize, y: isize) -> Color {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+
+    fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /**
+     * Encodes the picture as an uncompressed 24 bit BMP file.
+     */
+    pub fn to_bmp(&self) -> Vec<u8> {
+        const HEADERS_SIZE: usize = 14 + 40;
+        // rows are padded to a multiple of 4 bytes
+        let row_size = (self.width * 3).div_ceil(4) * 4;
+        let file_size = HEADERS_SIZE + row_size * self.height;
+        let mut bmp = Vec::with_capacity(file_size);
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&(file_size as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&(HEADERS_SIZE as u32).to_le_bytes());
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&(self.width as i32).to_le_bytes());
+        bmp.extend_from_slice(&(self.height as i32).to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&24u16.to_le_bytes());
+        // no compression, then the image size, resolution and palette fields left at 0
+        bmp.extend_from_slice(&[0; 24]);
+        // rows are stored bottom up, with pixels in BGR order
+        for row in self.pixels.chunks(self.width).rev() {
+            for color in row {
+                bmp.extend_from_slice(&[color.b, color.g, color.r]);
+            }
+            bmp.resize(bmp.len() + row_size - self.width * 3, 0);
+        }
+        bmp
+    }
+}
+
+/**
+ * Software scaling filters applied to the picture before it is shown, so they work without
+ * GPU shaders and show up in screenshots.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Filter {
+    None,
+    // also known as EPX
+    Scale2x,
+    Scale3x,
+    // smooths edges like hq2x by blending corners, using colour similarity rather than
+    // hq2x's lookup table
+    Hq2x,
+    // every other line darkened
+    Scanlines,
+    // an aperture grille of red, green and blue stripes with scanlines
+    Crt,
+}
+
+// the order the filter hotkey cycles through them
+const FILTERS: [(&str, Filter); 6] = [
+    ("none", Filter::None),
+    ("scale2x", Filter::Scale2x),
+    ("scale3x", Filter::Scale3x),
+    ("hq2x", Filter::Hq2x),
+    ("scanlines", Filter::Scanlines),
+    ("crt", Filter::Crt),
+];
+
+impl Filter {
+    /**
+     * Parses `none`, `scale2x` (or `epx`), `scale3x`, `hq2x`, `scanlines` or `crt`.
+     */
+    pub fn parse(name: &str) -> Result<Filter, String> {
+        if name == "epx" {
+            return Ok(Filter::Scale2x);
+        }
+        FILTERS
+            .iter()
+            .find(|(filter_name, _)| *filter_name == name)
+            .map(|(_, filter)| *filter)
+            .ok_or(format!(
+                "unknown filter {:?}, expected one of {}",
+                name,
+                FILTERS.map(|(name, _)| name).join(", ")
+            ))
+    }
+
+    pub fn name(&self) -> &'static str {
+        FILTERS
+            .iter()
+            .find(|(_, filter)| filter == self)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+
+    pub fn next(&self) -> Filter {
+        let idx = FILTERS
+            .iter()
+            .position(|(_, filter)| filter == self)
+            .unwrap();
+        FILTERS[(idx + 1) % FILTERS.len()].1
+    }
+
+    /**
+     * How many times larger the filtered picture is in each direction.
+     */
+    pub fn factor(&self) -> usize {
+        match self {
+            Filter::None => 1,
+            Filter::Scale2x | Filter::Hq2x | Filter::Scanlines => 2,
+            Filter::Scale3x | Filter::Crt => 3,
+        }
+    }
+
+    pub fn apply(&self, image: &Image) -> Image {
+        match self {
+            Filter::None => image.clone(),
+            Filter::Scale2x => scale2x(image),
+            Filter::Scale3x => scale3x(image),
+            Filter::Hq2x => hq2x(image),
+            Filter::Scanlines => scanlines(image),
+            Filter::Crt => crt(image),
+        }
+    }
+}
+
+/**
+ * Calls `fill` with the 3x3 neighbourhood of every pixel, row by row from the top left,
+ * and where its `factor` x `factor` block starts in the output.
+ */
+fn for_each_neighbourhood(
+    image: &Image,
+    factor: usize,
+    mut fill: impl FnMut(&mut Image, [[Color; 3]; 3], (usize, usize)),
+) -> Image {
+    let mut out = Image::new(image.width * factor, image.height * factor);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let (xi, yi) = (x as isize, y as isize);
+            let around = [-1, 0, 1].map(|dy| [-1, 0, 1].map(|dx| image.at(xi + dx, yi + dy)));
+            fill(&mut out, around, (x * factor, y * factor));
+        }
+    }
+    out
+}
+
+fn scale2x(image: &Image) -> Image {
+    for_each_neighbourhood(image, 2, |out, around, (x, y)| {
+        let [[_, a, _], [c, p, b], [_, d, _]] = around;
+        let pick = |cond: bool, color: Color| if cond { color } else { p };
+        out.set(x, y, pick(c == a && c != d && a != b, a));
+        out.set(x + 1, y, pick(a == b && a != c && b != d, b));
+        out.set(x, y + 1, pick(d == c && d != b && c != a, c));
+        out.set(x + 1, y + 1, pick(b == d && b != a && d != c, d));
+    })
+}
+
+fn scale3x(image: &Image) -> Image {
+    for_each_neighbourhood(image, 3, |out, around, (x, y)| {
+        let [[a, b, c], [d, e, f], [g, h, i]] = around;
+        let pick = |cond: bool, color: Color| if cond { color } else { e };
+        let block = [
+            [
+                pick(d == b && b != f && d != h, d),
+                pick(
+                    (d == b && b != f && d != h && e != c)
+                        || (b == f && b != d && f != h && e != a),
+                    b,
+                ),
+                pick(b == f && b != d && f != h, f),
+            ],
+            [
+                pick(
+                    (d == b && b != f && d != h && e != g)
+                        || (d == h && d != b && h != f && e != a),
+                    d,
+                ),
+                e,
+                pick(
+                    (b == f && b != d && f != h && e != i)
+                        || (h == f && d != h && b != f && e != c),
+                    f,
+                ),
+            ],
+            [
+                pick(d == h && d != b && h != f, d),
+                pick(
+                    (d == h && d != b && h != f && e != i)
+                        || (h == f && d != h && b != f && e != g),
+                    h,
+                ),
+                pick(h == f && d != h && b != f, f),
+            ],
+        ];
+        for (dy, row) in block.iter().enumerate() {
+            for (dx, color) in row.iter().enumerate() {
+                out.set(x + dx, y + dy, *color);
+            }
+        }
+    })
+}
+
+// the thresholds hqx uses to tell whether colours differ, in YUV
+fn similar(first: Color, second: Color) -> bool {
+    let yuv = |c: Color| {
+        let (r, g, b) = (c.r as f32, c.g as f32, c.b as f32);
+        (
+            0.299 * r + 0.587 * g + 0.114 * b,
+            -0.169 * r - 0.331 * g + 0.5 * b,
+            0.5 * r - 0.419 * g - 0.081 * b,
+        )
+    };
+    let (y1, u1, v1) = yuv(first);
+    let (y2, u2, v2) = yuv(second);
+    (y1 - y2).abs() <= 48.0 && (u1 - u2).abs() <= 7.0 && (v1 - v2).abs() <= 6.0
+}
+
+/**
+ * The colour `amount` of the way from `from` to `to`.
+ */
+pub fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let channel =
+        |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount).round() as u8;
+    Color::RGB(
+        channel(from.r, to.r),
+        channel(from.g, to.g),
+        channel(from.b, to.b),
+    )
+}
+
+fn hq2x(image: &Image) -> Image {
+    for_each_neighbourhood(image, 2, |out, around, (x, y)| {
+        let center = around[1][1];
+        for (dy, row) in [0, 2].iter().enumerate() {
+            for (dx, column) in [0, 2].iter().enumerate() {
+                let horizontal = around[1][*column];
+                let vertical = around[*row][1];
+                let diagonal = around[*row][*column];
+                // the corner lies on an edge between the neighbours' colour and the centre's
+                let color = if similar(horizontal, vertical) && !similar(center, horizontal) {
+                    let amount = if similar(diagonal, horizontal) {
+                        0.75
+                    } else {
+                        0.5
+                    };
+                    mix(center, horizontal, amount)
+                } else {
+                    center
+                };
+                out.set(x + dx, y + dy, color);
+            }
+        }
+    })
+}
+
+fn dim(color: Color, amount: f32) -> Color {
+    mix(Color::BLACK, color, amount)
+}
+
+fn scanlines(image: &Image) -> Image {
+    for_each_neighbourhood(image, 2, |out, around, (x, y)| {
+        let color = around[1][1];
+        out.set(x, y, color);
+        out.set(x + 1, y, color);
+        out.set(x, y + 1, dim(color, 0.5));
+        out.set(x + 1, y + 1, dim(color, 0.5));
+    })
+}
+
+fn crt(image: &Image) -> Image {
+    for_each_neighbourhood(image, 3, |out, around, (x, y)| {
+        let color = around[1][1];
+        let stripes = [
+            Color::RGB(color.r, dim(color, 0.4).g, dim(color, 0.4).b),
+            Color::RGB(dim(color, 0.4).r, color.g, dim(color, 0.4).b),
+            Color::RGB(dim(color, 0.4).r, dim(color, 0.4).g, color.b),
+        ];
+        for dy in 0..3 {
+            for (dx, stripe) in stripes.iter().enumerate() {
+                let stripe = if dy == 2 { dim(*stripe, 0.6) } else { *stripe };
+                out.set(x + dx, y + dy, stripe);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+#[path = "./filters_test.rs"]
+mod filters_test;
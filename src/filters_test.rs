@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use sdl2::pixels::Color;
+
+    use crate::filters::{Filter, Image};
+
+    const ON: Color = Color::WHITE;
+    const OFF: Color = Color::BLACK;
+
+    fn image(rows: &[&str]) -> Image {
+        Image {
+            width: rows[0].len(),
+            height: rows.len(),
+            pixels: rows
+                .iter()
+                .flat_map(|row| row.chars().map(|c| if c == '#' { ON } else { OFF }))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_scale2x_fills_corners_between_matching_neighbours() {
+        let scaled = Filter::Scale2x.apply(&image(&["#.", ".#"]));
+        assert_eq!(scaled, image(&["##..", "#.#.", ".#.#", "..##"]));
+    }
+
+    #[test]
+    fn test_scale3x_keeps_flat_areas() {
+        let scaled = Filter::Scale3x.apply(&image(&["##", "##"]));
+        assert_eq!(scaled, image(&["######"; 6]));
+    }
+
+    #[test]
+    fn test_filters_scale_by_their_factor() {
+        let original = image(&["#..", ".#.", "..#", "#.#"]);
+        for name in ["none", "epx", "scale3x", "hq2x", "scanlines", "crt"] {
+            let filter = Filter::parse(name).unwrap();
+            let filtered = filter.apply(&original);
+            assert_eq!(filtered.width, original.width * filter.factor());
+            assert_eq!(filtered.height, original.height * filter.factor());
+            assert_eq!(filtered.pixels.len(), filtered.width * filtered.height);
+        }
+    }
+
+    #[test]
+    fn test_next_cycles_through_filters() {
+        let mut filter = Filter::None;
+        for _ in 0..6 {
+            filter = filter.next();
+        }
+        assert_eq!(filter, Filter::None);
+    }
+
+    #[test]
+    fn test_to_bmp_pads_rows() {
+        let bmp = image(&["#"]).to_bmp();
+        assert_eq!(&bmp[..2], b"BM");
+        // headers, then one BGR pixel padded to 4 bytes
+        assert_eq!(bmp.len(), 54 + 4);
+        assert_eq!(&bmp[54..], &[255, 255, 255, 0]);
+    }
+}
@@ -0,0 +1,200 @@
+use std::fs;
+
+use crate::hexadecimal_sprites::{HEXADECIMAL_SPRITES, HEX_SIZE};
+
+pub const SMALL_FONT_SIZE: usize = 16 * HEX_SIZE;
+// bytes per digit of the 8x10 fonts SCHIP draws scores with
+pub const LARGE_DIGIT_SIZE: usize = 10;
+
+// the built-in fonts, the first is the default
+const FONT_NAMES: [&str; 6] = ["chip48", "vip", "dream6800", "eti660", "schip", "octo"];
+
+// the COSMAC VIP's, with its squared off 4, 7, B and D
+const VIP: [u8; SMALL_FONT_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x60, 0x20, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+// the DREAM 6800's, 3 pixels wide
+const DREAM_6800: [u8; SMALL_FONT_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0x40, 0x40, 0x40, 0x40, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+// the ETI-660's, 3 pixels wide with lowercase b and d
+const ETI_660: [u8; SMALL_FONT_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x20, 0x20, 0x20, 0x20, 0x20, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0xA0, 0xA0, 0xE0, 0x20, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0x80, 0x80, 0xE0, 0xA0, 0xE0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0x20, 0x20, 0xE0, 0xA0, 0xE0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+// SCHIP's big digits, only 0 to 9
+const SCHIP_LARGE: [u8; 10 * LARGE_DIGIT_SIZE] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+];
+
+// Octo's big digits, 0 to F
+const OCTO_LARGE: [u8; 16 * LARGE_DIGIT_SIZE] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+/**
+ * The sprites of the hex digits FX29 points I at, and for SCHIP the big digits, which are
+ * loaded right after them.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Font {
+    pub name: String,
+    pub small: [u8; SMALL_FONT_SIZE],
+    pub large: Vec<u8>,
+}
+
+impl Font {
+    /**
+     * The CHIP-48 font, which SCHIP and Octo kept and most interpreters since use.
+     */
+    pub fn new() -> Font {
+        Font {
+            name: "chip48".to_string(),
+            small: HEXADECIMAL_SPRITES.concat().try_into().unwrap(),
+            large: vec![],
+        }
+    }
+
+    pub fn builtin(name: &str) -> Result<Font, String> {
+        let (small, large) = match name {
+            "chip48" => (Font::new().small, &[][..]),
+            "vip" => (VIP, &[][..]),
+            "dream6800" => (DREAM_6800, &[][..]),
+            "eti660" => (ETI_660, &[][..]),
+            "schip" => (Font::new().small, &SCHIP_LARGE[..]),
+            "octo" => (Font::new().small, &OCTO_LARGE[..]),
+            _ => {
+                return Err(format!(
+                    "unknown font {:?}, expected one of {} or a font file",
+                    name,
+                    FONT_NAMES.join(", ")
+                ))
+            }
+        };
+        Ok(Font {
+            name: name.to_string(),
+            small,
+            large: large.to_vec(),
+        })
+    }
+
+    /**
+     * A font from a file of 16 five-byte digits, optionally followed by 10 or 16 ten-byte
+     * big digits.
+     */
+    pub fn parse(name: &str, bytes: &[u8]) -> Result<Font, String> {
+        let large = bytes.get(SMALL_FONT_SIZE..).unwrap_or_default();
+        let large_sizes = [0, 10 * LARGE_DIGIT_SIZE, 16 * LARGE_DIGIT_SIZE];
+        if bytes.len() < SMALL_FONT_SIZE || !large_sizes.contains(&large.len()) {
+            return Err(format!(
+                "expected {} bytes, or {} or {} with big digits, but the font is {} bytes",
+                SMALL_FONT_SIZE,
+                SMALL_FONT_SIZE + 10 * LARGE_DIGIT_SIZE,
+                SMALL_FONT_SIZE + 16 * LARGE_DIGIT_SIZE,
+                bytes.len()
+            ));
+        }
+        Ok(Font {
+            name: name.to_string(),
+            small: bytes[..SMALL_FONT_SIZE].try_into().unwrap(),
+            large: large.to_vec(),
+        })
+    }
+
+    /**
+     * Loads a built-in font by name or else a font file.
+     */
+    pub fn load(spec: &str) -> Result<Font, String> {
+        if let Ok(font) = Font::builtin(spec) {
+            return Ok(font);
+        }
+        let bytes = fs::read(spec).map_err(|e| format!("{}: {}", spec, e))?;
+        Font::parse(spec, &bytes).map_err(|e| format!("{}: {}", spec, e))
+    }
+
+    // in bytes
+    pub fn size(&self) -> usize {
+        SMALL_FONT_SIZE + self.large.len()
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Font::new()
+    }
+}
+
+#[cfg(test)]
+#[path = "./fonts_test.rs"]
+mod fonts_test;
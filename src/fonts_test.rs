@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use crate::fonts::{Font, SMALL_FONT_SIZE};
+    use crate::hexadecimal_sprites::HEXADECIMAL_SPRITES;
+
+    #[test]
+    fn test_default_is_the_original_font() {
+        let font = Font::new();
+        assert_eq!(font.small[..], HEXADECIMAL_SPRITES.concat()[..]);
+        assert_eq!(font.size(), SMALL_FONT_SIZE);
+        assert_eq!(Font::builtin("chip48"), Ok(font));
+    }
+
+    #[test]
+    fn test_builtin() {
+        let vip = Font::builtin("vip").unwrap();
+        // the VIP's 7 has no slant
+        assert_eq!(vip.small[35..40], [0xF0, 0x10, 0x10, 0x10, 0x10]);
+        assert_eq!(
+            Font::builtin("schip").unwrap().size(),
+            SMALL_FONT_SIZE + 100
+        );
+        assert_eq!(Font::builtin("octo").unwrap().size(), SMALL_FONT_SIZE + 160);
+        assert!(Font::builtin("comic-sans").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let font = Font::parse("mine", &[0x80; SMALL_FONT_SIZE]).unwrap();
+        assert_eq!(font.small, [0x80; SMALL_FONT_SIZE]);
+        assert!(font.large.is_empty());
+        let font = Font::parse("mine", &[0x80; SMALL_FONT_SIZE + 100]).unwrap();
+        assert_eq!(font.large.len(), 100);
+        assert!(Font::parse("mine", &[0x80; SMALL_FONT_SIZE - 1]).is_err());
+        assert!(Font::parse("mine", &[0x80; SMALL_FONT_SIZE + 50]).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::displays::{Drawable, GamePixels};
+use crate::input_driver::{EventPollable, TimedEvent};
+
+/**
+ * A display without a window, for running ROMs in tests, scripts and benchmarks.
+ */
+pub struct HeadlessDisplay {
+    pixels: GamePixels,
+}
+
+impl HeadlessDisplay {
+    pub fn new() -> HeadlessDisplay {
+        HeadlessDisplay {
+            pixels: GamePixels::new(),
+        }
+    }
+}
+
+impl Default for HeadlessDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drawable for HeadlessDisplay {
+    fn draw_at(&mut self, bytes: &[u8], start_coord: (usize, usize)) -> bool {
+        self.pixels.fill_in_bytes(bytes, start_coord)
+    }
+
+    fn clear(&mut self) {
+        self.pixels = GamePixels::new();
+    }
+
+    fn pixels(&self) -> &GamePixels {
+        &self.pixels
+    }
+
+    fn restore(&mut self, pixels: GamePixels) {
+        self.pixels = pixels;
+    }
+}
+
+/**
+ * Input that never presses anything. A program waiting for a key stops instead.
+ */
+pub struct HeadlessInput;
+
+impl EventPollable for HeadlessInput {
+    fn poll(&mut self, _now: u64) -> Vec<TimedEvent> {
+        vec![]
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+#[path = "./headless_test.rs"]
+mod headless_test;
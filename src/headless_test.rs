@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use crate::displays::Drawable;
+    use crate::headless::{HeadlessDisplay, HeadlessInput};
+    use crate::interpreters::Interpreter;
+    use crate::memory::Memory;
+    use crate::timers::DelayTimer;
+
+    #[test]
+    fn test_to_text() {
+        let mut display = HeadlessDisplay::new();
+        display.draw_at(&[0b1100_0000, 0b1010_0000], (0, 0));
+        let text = display.pixels().to_text();
+        assert!(text.starts_with("█▀▄\n"));
+        assert_eq!(text.lines().count(), 16);
+    }
+
+    fn run(program: &[u8]) -> Interpreter<HeadlessDisplay, HeadlessInput, DelayTimer, DelayTimer> {
+        let mut interpreter = Interpreter::new(
+            Memory::from_rom(program, 0x200).unwrap(),
+            HeadlessDisplay::new(),
+            HeadlessInput,
+            DelayTimer { value: 0 },
+            DelayTimer { value: 0 },
+        );
+        interpreter.set_throttled(false);
+        while !interpreter.is_halted() {
+            if interpreter.run_frame().is_err() {
+                break;
+            }
+        }
+        interpreter
+    }
+
+    #[test]
+    fn test_runs_until_halted() {
+        // draws the digit 0 and jumps to itself
+        let interpreter = run(&[0xF0, 0x29, 0xD0, 0x05, 0x12, 0x04]);
+        assert_eq!(interpreter.state().pc, 0x204);
+        assert!(interpreter
+            .display()
+            .pixels()
+            .to_text()
+            .starts_with("█▀▀█\n"));
+    }
+
+    #[test]
+    fn test_waiting_for_a_key_stops() {
+        let interpreter = run(&[0x60, 0x01, 0xF0, 0x0A, 0x12, 0x04]);
+        assert_eq!(interpreter.state().pc, 0x202);
+        assert_eq!(interpreter.state().v[0], 1);
+    }
+}
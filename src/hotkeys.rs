@@ -0,0 +1,126 @@
+use std::fs;
+
+use sdl2::keyboard::Scancode;
+
+use crate::input_driver::Hotkey;
+
+const HOTKEY_NAMES: [(&str, Hotkey); 18] = [
+    ("quit", Hotkey::Quit),
+    ("pause", Hotkey::Pause),
+    ("frame-advance", Hotkey::FrameAdvance),
+    ("reset", Hotkey::Reset),
+    ("fast-forward", Hotkey::FastForward),
+    ("turbo", Hotkey::ToggleTurbo),
+    ("palette", Hotkey::CyclePalette),
+    ("phosphor", Hotkey::TogglePhosphor),
+    ("blend", Hotkey::ToggleBlend),
+    ("filter", Hotkey::CycleFilter),
+    ("grid", Hotkey::ToggleGrid),
+    ("rotate", Hotkey::Rotate),
+    ("flip", Hotkey::Flip),
+    ("fullscreen", Hotkey::ToggleFullscreen),
+    ("scaling", Hotkey::ToggleScaling),
+    ("screenshot", Hotkey::Screenshot),
+    ("save-settings", Hotkey::SaveSettings),
+    ("mute", Hotkey::ToggleMute),
+];
+
+const DEFAULT_BINDINGS: [(Scancode, Hotkey); 18] = [
+    (Scancode::Escape, Hotkey::Quit),
+    (Scancode::F1, Hotkey::Pause),
+    (Scancode::F10, Hotkey::FrameAdvance),
+    (Scancode::Backspace, Hotkey::Reset),
+    (Scancode::Tab, Hotkey::FastForward),
+    (Scancode::Grave, Hotkey::ToggleTurbo),
+    (Scancode::F2, Hotkey::CyclePalette),
+    (Scancode::F3, Hotkey::TogglePhosphor),
+    (Scancode::F4, Hotkey::ToggleBlend),
+    (Scancode::F5, Hotkey::CycleFilter),
+    (Scancode::F6, Hotkey::ToggleGrid),
+    (Scancode::F7, Hotkey::Rotate),
+    (Scancode::F8, Hotkey::Flip),
+    (Scancode::F11, Hotkey::ToggleFullscreen),
+    (Scancode::F12, Hotkey::ToggleScaling),
+    (Scancode::PrintScreen, Hotkey::Screenshot),
+    (Scancode::F9, Hotkey::SaveSettings),
+    (Scancode::M, Hotkey::ToggleMute),
+];
+
+/**
+ * Which keys trigger the emulator's hotkeys, by scancode like the keymap.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hotkeys {
+    bindings: Vec<(Scancode, Hotkey)>,
+}
+
+impl Hotkeys {
+    pub fn new() -> Hotkeys {
+        Hotkeys {
+            bindings: DEFAULT_BINDINGS.to_vec(),
+        }
+    }
+
+    /**
+     * Parses a hotkeys file on top of `base`, in the format of keymap files with hotkey
+     * names instead of CHIP-8 keys, e.g. `pause = P, Pause`.
+     */
+    pub fn parse(text: &str, base: Hotkeys) -> Result<Hotkeys, String> {
+        let mut hotkeys = base;
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, scancodes) = line
+                .split_once('=')
+                .ok_or(format!("line {}: expected `hotkey = scancodes`", idx + 1))?;
+            let (name, scancodes) = (name.trim(), scancodes.trim());
+            let (_, hotkey) = HOTKEY_NAMES
+                .iter()
+                .find(|(hotkey_name, _)| *hotkey_name == name)
+                .ok_or(format!(
+                    "line {}: unknown hotkey {:?}, expected one of {}",
+                    idx + 1,
+                    name,
+                    HOTKEY_NAMES.map(|(name, _)| name).join(", ")
+                ))?;
+            hotkeys.bindings.retain(|(_, bound)| bound != hotkey);
+            for name in scancodes
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                let scancode = Scancode::from_name(name).ok_or(format!(
+                    "line {}: unknown scancode {:?}",
+                    idx + 1,
+                    name
+                ))?;
+                hotkeys.bindings.push((scancode, *hotkey));
+            }
+        }
+        Ok(hotkeys)
+    }
+
+    pub fn load(path: &str) -> Result<Hotkeys, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Hotkeys::parse(&text, Hotkeys::new()).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn hotkey_for(&self, scancode: Scancode) -> Option<Hotkey> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == scancode)
+            .map(|(_, hotkey)| *hotkey)
+    }
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Hotkeys::new()
+    }
+}
+
+#[cfg(test)]
+#[path = "./hotkeys_test.rs"]
+mod hotkeys_test;
@@ -0,0 +1,30 @@
+#[cfg(test)]
+mod tests {
+    use sdl2::keyboard::Scancode;
+
+    use crate::hotkeys::Hotkeys;
+    use crate::input_driver::Hotkey;
+
+    #[test]
+    fn test_defaults() {
+        let hotkeys = Hotkeys::new();
+        assert_eq!(hotkeys.hotkey_for(Scancode::Escape), Some(Hotkey::Quit));
+        assert_eq!(hotkeys.hotkey_for(Scancode::Q), None);
+    }
+
+    #[test]
+    fn test_parse_rebinds() {
+        let hotkeys = Hotkeys::parse("pause = P, Pause\nquit =", Hotkeys::new()).unwrap();
+        assert_eq!(hotkeys.hotkey_for(Scancode::P), Some(Hotkey::Pause));
+        assert_eq!(hotkeys.hotkey_for(Scancode::Pause), Some(Hotkey::Pause));
+        assert_eq!(hotkeys.hotkey_for(Scancode::F1), None);
+        assert_eq!(hotkeys.hotkey_for(Scancode::Escape), None);
+        assert_eq!(hotkeys.hotkey_for(Scancode::Tab), Some(Hotkey::FastForward));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Hotkeys::parse("rewind = R", Hotkeys::new()).is_err());
+        assert!(Hotkeys::parse("pause = NotAKey", Hotkeys::new()).is_err());
+    }
+}
@@ -1,71 +1,291 @@
 use sdl2;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::EventPump;
+use sdl2::controller::GameController;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Scancode;
+use sdl2::mouse::MouseButton;
+use sdl2::{EventPump, GameControllerSubsystem};
 
-use crate::keypads::Keypad;
+use crate::controllers::ControllerMap;
+use crate::hotkeys::Hotkeys;
+use crate::keymaps::Keymap;
+
+/**
+ * Emulator functions bound to keys outside the CHIP-8 keypad.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Hotkey {
+    Quit,
+    Pause,
+    FrameAdvance,
+    Reset,
+    // held rather than pressed, runs faster until released
+    FastForward,
+    ToggleTurbo,
+    CyclePalette,
+    TogglePhosphor,
+    ToggleBlend,
+    ToggleGrid,
+    Rotate,
+    Flip,
+    ToggleScaling,
+    ToggleFullscreen,
+    CycleFilter,
+    Screenshot,
+    // writes the current settings to the config file for the loaded ROM
+    SaveSettings,
+    ToggleMute,
+}
+
+/**
+ * What happened to the emulator's input.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InputEvent {
+    KeyDown(u8),
+    KeyUp(u8),
+    // pressed (`true`) or released (`false`)
+    Hotkey(Hotkey, bool),
+    Quit,
+}
+
+/**
+ * An input event with the emulated time it was polled at, in instructions executed since
+ * the start.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimedEvent {
+    pub at: u64,
+    pub event: InputEvent,
+}
+
+/**
+ * Where the mouse or a finger is pressing on the game's window.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Pointer {
+    // in window coordinates
+    Mouse(i32, i32),
+    // as fractions of the window's width and height, as SDL reports touches
+    Finger(f32, f32),
+}
+
+// the mouse id of mouse events SDL synthesizes from touches, which are handled as touches
+const TOUCH_MOUSE_ID: u32 = u32::MAX;
 
 pub trait EventPollable {
-    fn poll(&mut self) -> Result<Keypad, ()>;
+    /**
+     * The input events since the last call in the order they happened, stamped with `now`.
+     * Keys pressed and released in between show up as a `KeyDown` followed by a `KeyUp`.
+     */
+    fn poll(&mut self, now: u64) -> Vec<TimedEvent>;
+
+    /**
+     * The mouse buttons and fingers currently held down on the game's window.
+     */
+    fn pointers(&self) -> Vec<Pointer> {
+        vec![]
+    }
+
+    /**
+     * Events for a window other than the game's that were collected while polling.
+     */
+    fn window_events(&mut self, _window_id: u32) -> Vec<Event> {
+        vec![]
+    }
+
+    /**
+     * Whether a player can press keys, so a program waiting for one doesn't wait forever.
+     */
+    fn is_interactive(&self) -> bool {
+        true
+    }
 }
 
 pub struct InputDriver {
     event_pump: EventPump,
+    // windows whose events are kept for `window_events`
+    forwarded_windows: Vec<u32>,
+    forwarded_events: Vec<Event>,
+    hotkey_bindings: Hotkeys,
+    keymap: Keymap,
+    // the keys held down on the keyboard, in the game's window
+    held_scancodes: Vec<Scancode>,
+    // the CHIP-8 keys reported held so far, to turn changes into events
+    held_keys: [bool; 16],
+    // `None` if SDL couldn't initialize game controller support
+    controller_subsystem: Option<GameControllerSubsystem>,
+    // the connected controllers, opened and closed as they are plugged in and out
+    controllers: Vec<GameController>,
+    controller_map: ControllerMap,
+    // where the left mouse button is held down
+    mouse: Option<(i32, i32)>,
+    fingers: Vec<(i64, f32, f32)>,
 }
 
 impl InputDriver {
-    pub fn new(sdl: &sdl2::Sdl) -> InputDriver {
+    pub fn new(
+        sdl: &sdl2::Sdl,
+        keymap: Keymap,
+        controller_map: ControllerMap,
+        hotkey_bindings: Hotkeys,
+    ) -> InputDriver {
         InputDriver {
             event_pump: sdl.event_pump().unwrap(),
+            forwarded_windows: Vec::new(),
+            forwarded_events: Vec::new(),
+            hotkey_bindings,
+            keymap,
+            held_scancodes: Vec::new(),
+            held_keys: [false; 16],
+            controller_subsystem: sdl.game_controller().ok(),
+            controllers: Vec::new(),
+            controller_map,
+            mouse: None,
+            fingers: Vec::new(),
         }
     }
-}
 
-impl EventPollable for InputDriver {
-    fn poll(&mut self) -> Result<Keypad, ()> {
-        for event in self.event_pump.poll_iter() {
-            if let Event::Quit { .. } = event {
-                return Err(());
+    // SDL sends an added event for every controller already connected at startup too
+    fn open_controller(&mut self, joystick_index: u32) {
+        if let Some(subsystem) = &self.controller_subsystem {
+            match subsystem.open(joystick_index) {
+                Ok(controller) => self.controllers.push(controller),
+                Err(e) => eprintln!("couldn't open game controller {}: {}", joystick_index, e),
             }
         }
+    }
 
-        let keys: Vec<Keycode> = self
-            .event_pump
-            .keyboard_state()
-            .pressed_scancodes()
-            .filter_map(Keycode::from_scancode)
-            .collect();
-
-        let mut chip8_keys = [false; 16];
-        let mut last_pressed: Option<u8> = None;
-        for key in keys {
-            let index: Option<usize> = match key {
-                Keycode::Q => Some(0x1),
-                Keycode::W => Some(0x2),
-                Keycode::E => Some(0x3),
-                Keycode::R => Some(0xc),
-                Keycode::A => Some(0x4),
-                Keycode::S => Some(0x5),
-                Keycode::D => Some(0x6),
-                Keycode::F => Some(0xd),
-                Keycode::U => Some(0x7),
-                Keycode::I => Some(0x8),
-                Keycode::O => Some(0x9),
-                Keycode::P => Some(0xe),
-                Keycode::J => Some(0xa),
-                Keycode::K => Some(0x0),
-                Keycode::L => Some(0xb),
-                Keycode::Semicolon => Some(0xf),
-                _ => None,
+    /**
+     * Adds a `KeyDown` or `KeyUp` for each CHIP-8 key the keyboard and the controllers
+     * press or release since the last call.
+     */
+    fn push_key_changes(&mut self, events: &mut Vec<TimedEvent>, now: u64) {
+        let mut keys = [false; 16];
+        for scancode in &self.held_scancodes {
+            for key in self.keymap.chip8_keys(*scancode) {
+                keys[key as usize] = true;
+            }
+        }
+        for controller in &self.controllers {
+            self.controller_map.press(controller, &mut keys);
+        }
+        for (key, (held, was_held)) in keys.iter().zip(self.held_keys).enumerate() {
+            let event = match (held, was_held) {
+                (true, false) => InputEvent::KeyDown(key as u8),
+                (false, true) => InputEvent::KeyUp(key as u8),
+                _ => continue,
             };
-            if let Some(chip_idx) = index {
-                last_pressed = Some(chip_idx as u8);
-                chip8_keys[chip_idx] = true;
+            events.push(TimedEvent { at: now, event });
+        }
+        self.held_keys = keys;
+    }
+
+    /**
+     * Keep the events of `window_id` around for `window_events` instead of dropping them.
+     */
+    pub fn forward_window(&mut self, window_id: u32) {
+        self.forwarded_windows.push(window_id);
+    }
+}
+
+impl EventPollable for InputDriver {
+    fn pointers(&self) -> Vec<Pointer> {
+        let fingers = self.fingers.iter().map(|(_, x, y)| Pointer::Finger(*x, *y));
+        self.mouse
+            .map(|(x, y)| Pointer::Mouse(x, y))
+            .into_iter()
+            .chain(fingers)
+            .collect()
+    }
+
+    fn window_events(&mut self, window_id: u32) -> Vec<Event> {
+        let (events, others) = self
+            .forwarded_events
+            .drain(..)
+            .partition(|event| event.get_window_id() == Some(window_id));
+        self.forwarded_events = others;
+        events
+    }
+
+    fn poll(&mut self, now: u64) -> Vec<TimedEvent> {
+        let mut timed = Vec::new();
+        let events: Vec<Event> = self.event_pump.poll_iter().collect();
+        for event in events {
+            let mut push = |event| timed.push(TimedEvent { at: now, event });
+            match event {
+                Event::Quit { .. } => push(InputEvent::Quit),
+                Event::ControllerDeviceAdded { which, .. } => self.open_controller(which),
+                Event::ControllerDeviceRemoved { which, .. } => self
+                    .controllers
+                    .retain(|controller| controller.instance_id() != which),
+                event
+                    if event
+                        .get_window_id()
+                        .is_some_and(|id| self.forwarded_windows.contains(&id)) =>
+                {
+                    self.forwarded_events.push(event)
+                }
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    repeat,
+                    ..
+                } => {
+                    if !repeat {
+                        if let Some(hotkey) = self.hotkey_bindings.hotkey_for(scancode) {
+                            push(InputEvent::Hotkey(hotkey, true));
+                        }
+                    }
+                    if !self.held_scancodes.contains(&scancode) {
+                        self.held_scancodes.push(scancode);
+                    }
+                }
+                Event::KeyUp {
+                    scancode: Some(scancode),
+                    ..
+                } => {
+                    if let Some(hotkey) = self.hotkey_bindings.hotkey_for(scancode) {
+                        push(InputEvent::Hotkey(hotkey, false));
+                    }
+                    self.held_scancodes.retain(|held| *held != scancode);
+                }
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    which,
+                    x,
+                    y,
+                    ..
+                } if which != TOUCH_MOUSE_ID => self.mouse = Some((x, y)),
+                Event::MouseMotion { which, x, y, .. }
+                    if which != TOUCH_MOUSE_ID && self.mouse.is_some() =>
+                {
+                    self.mouse = Some((x, y))
+                }
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Left,
+                    ..
+                } => self.mouse = None,
+                Event::FingerDown {
+                    finger_id, x, y, ..
+                }
+                | Event::FingerMotion {
+                    finger_id, x, y, ..
+                } => {
+                    self.fingers.retain(|(id, _, _)| *id != finger_id);
+                    self.fingers.push((finger_id, x, y));
+                }
+                Event::FingerUp { finger_id, .. } => {
+                    self.fingers.retain(|(id, _, _)| *id != finger_id)
+                }
+                // with several windows open SDL only quits once the last one is closed
+                Event::Window {
+                    win_event: WindowEvent::Close,
+                    ..
+                } => push(InputEvent::Quit),
+                _ => (),
             }
+            // after every event, so a key tapped between two polls is both pressed and released
+            self.push_key_changes(&mut timed, now);
         }
-        Ok(Keypad {
-            keypad: chip8_keys,
-            last_pressed: last_pressed,
-        })
+        timed
     }
 }
@@ -1,17 +1,30 @@
-use std::time::Duration;
+use std::fmt;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use rand::Rng;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 use unwrap::unwrap;
 
 extern crate sdl2;
 
-use crate::displays::Drawable;
-use crate::input_driver::EventPollable;
+use crate::configs::{Settings, SettingsStore};
+use crate::debuggers::{Access, DebugEvent, Debugger, Stop, WatchHit};
+use crate::displays::{Drawable, GRID_HEIGHT, GRID_WIDTH};
+use crate::input_driver::{EventPollable, Hotkey, InputEvent, TimedEvent};
+use crate::journals::{Journal, JournalEntry, PixelsUndo};
 use crate::keypads::Keypad;
 use crate::memory::{Memory, STARTING_MEMORY};
+use crate::profilers::{Flow, Profiler};
+use crate::quirks::Quirks;
 use crate::timers::TimerActions;
 
 const OPCODE_SIZE: u16 = 2;
+// timers are decremented once per frame, i.e. 60 times per second.
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: u32 = 16;
+// how many times faster than normal fast-forward and turbo run by default
+pub const DEFAULT_FAST_FORWARD: u32 = 4;
+pub const DEFAULT_TURBO: u32 = 2;
 
 pub struct Interpreter<D, I, ST, DT>
 where
@@ -21,13 +34,46 @@ where
     DT: TimerActions,
 {
     memory: Memory,
+    // the memory as loaded, restored by a soft reset
+    initial_memory: Memory,
     registers: Registers,
     stack: Stack,
     display: D,
-    keypad: [bool; 16],
+    keypad: Keypad,
+    // the keys pressed on the display's on-screen keypad
+    touched: [bool; 16],
     input_driver: I,
     sound_timer: ST,
     delay_timer: DT,
+    profiler: Option<Profiler>,
+    debugger: Debugger,
+    // instructions executed in the current frame
+    frame_progress: u32,
+    journal: Option<Journal>,
+    // what the instruction being executed changed
+    journal_entry: Option<JournalEntry>,
+    paused: bool,
+    // run one frame while paused
+    frame_advance: bool,
+    // handled between instructions so an instruction waiting for a key isn't left half done
+    reset_requested: bool,
+    quit_requested: bool,
+    fast_forward_held: bool,
+    turbo: bool,
+    // speed multipliers while fast-forward is held and while turbo is on
+    fast_forward_speed: u32,
+    turbo_speed: u32,
+    // instructions executed since start, to sleep only every `speed` of them
+    instructions: u64,
+    instructions_per_frame: u32,
+    quirks: Quirks,
+    // where the save settings hotkey saves to, if there is a config file
+    settings_store: Option<SettingsStore>,
+    // where the program starts, and where a reset starts it again
+    start_address: u16,
+    rng: SmallRng,
+    // sleep between instructions to run at the game's speed, off to run as fast as possible
+    throttled: bool,
 }
 
 impl<D, I, ST, DT> Interpreter<D, I, ST, DT>
@@ -45,66 +91,555 @@ where
         delay_timer: DT,
     ) -> Interpreter<D, I, ST, DT> {
         let stack = [0; 16];
-        let registers = Registers::new();
-        let keypad = [false; 16];
+        let registers = Registers::new(STARTING_MEMORY);
+        // a tap shows as pressed for at least a frame
+        let keypad = Keypad::new(DEFAULT_INSTRUCTIONS_PER_FRAME as u64);
         Interpreter {
+            initial_memory: memory.clone(),
             memory,
             registers,
             stack,
             display,
             keypad,
+            touched: [false; 16],
             input_driver,
             sound_timer,
             delay_timer,
+            profiler: None,
+            debugger: Debugger::new(),
+            frame_progress: 0,
+            journal: None,
+            journal_entry: None,
+            paused: false,
+            frame_advance: false,
+            reset_requested: false,
+            quit_requested: false,
+            fast_forward_held: false,
+            turbo: false,
+            fast_forward_speed: DEFAULT_FAST_FORWARD,
+            turbo_speed: DEFAULT_TURBO,
+            instructions: 0,
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            quirks: Quirks::default(),
+            settings_store: None,
+            start_address: STARTING_MEMORY,
+            rng: SmallRng::from_entropy(),
+            throttled: true,
         }
     }
 
-    pub fn execute_program(&mut self) {
-        let frame_sleep = Duration::from_millis(1);
-        let mut fc = 0;
-        while let Ok(Keypad { keypad, .. }) = self.input_driver.poll() {
+    /**
+     * Starts the program at `address` rather than at 0x200.
+     */
+    pub fn set_start_address(&mut self, address: u16) {
+        self.start_address = address;
+        self.registers.pc = address;
+    }
+
+    /**
+     * Makes RND return the same numbers on every run.
+     */
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+    }
+
+    /**
+     * Whether to run at the game's speed, or as fast as possible without sleeping.
+     */
+    pub fn set_throttled(&mut self, throttled: bool) {
+        self.throttled = throttled;
+    }
 
-            std::thread::sleep(frame_sleep);
+    /**
+     * The number of instructions executed since the program started.
+     */
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /**
+     * Whether the program is stuck in a jump to itself, which is how many programs end.
+     */
+    pub fn is_halted(&self) -> bool {
+        let opcode = self.memory.read_instruction(self.registers.pc);
+        matches!(Opcodes::from_bytes(opcode), Some(Opcodes::JP(Addr(addr))) if addr == self.registers.pc)
+    }
+
+    pub fn display(&self) -> &D {
+        &self.display
+    }
 
-            self.keypad = keypad;
+    /**
+     * How many instructions to execute per 60 Hz frame.
+     */
+    pub fn set_instructions_per_frame(&mut self, instructions: u32) {
+        self.instructions_per_frame = instructions.max(1);
+        self.keypad = Keypad::new(self.instructions_per_frame as u64);
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn set_settings_store(&mut self, store: SettingsStore) {
+        self.settings_store = Some(store);
+    }
+
+    /**
+     * Saves the current speed, quirks and display settings as the settings of the loaded
+     * ROM.
+     */
+    fn save_settings(&self) {
+        let Some(store) = &self.settings_store else {
+            eprintln!("there is no config file to save the settings to");
+            return;
+        };
+        let mut settings = Settings {
+            speed: Some(self.instructions_per_frame),
+            fast_forward: Some(self.fast_forward_speed),
+            turbo: Some(self.turbo_speed),
+            quirks: Some(self.quirks),
+            ..store.settings.clone()
+        };
+        self.display.record_settings(&mut settings);
+        self.sound_timer.record_settings(&mut settings);
+        match store.save(&settings) {
+            Ok(()) => eprintln!(
+                "saved the settings for this ROM to {}",
+                store.path.display()
+            ),
+            Err(e) => eprintln!("couldn't save the settings: {}", e),
+        }
+    }
+
+    /**
+     * How many times faster than normal to run while fast-forward is held and while turbo
+     * is toggled on.
+     */
+    pub fn set_speeds(&mut self, fast_forward: u32, turbo: u32) {
+        self.fast_forward_speed = fast_forward.max(1);
+        self.turbo_speed = turbo.max(1);
+    }
 
-            let opcode = self.memory.read_instruction(self.registers.pc);
-            match self.execute_opcode(opcode as u16) {
-                ProgramCounter::Next => self.registers.pc += OPCODE_SIZE,
-                ProgramCounter::Skip(true) => self.registers.pc += 2 * OPCODE_SIZE,
-                ProgramCounter::Skip(false) => self.registers.pc += OPCODE_SIZE,
-                ProgramCounter::Jump(addr) => self.registers.pc = addr as u16,
+    fn speed(&self) -> u32 {
+        if self.fast_forward_held {
+            self.fast_forward_speed
+        } else if self.turbo {
+            self.turbo_speed
+        } else {
+            1
+        }
+    }
+
+    /**
+     * Restarts the program as if it was just loaded, keeping the debugger and display
+     * settings.
+     */
+    pub fn reset(&mut self) {
+        self.memory = self.initial_memory.clone();
+        self.registers = Registers::new(self.start_address);
+        self.stack = [0; 16];
+        self.delay_timer.set(0);
+        self.sound_timer.set(0);
+        self.frame_progress = 0;
+        self.display.clear();
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+        self.reset_requested = false;
+    }
+
+    pub fn state(&self) -> MachineState {
+        MachineState {
+            v: self.registers.v,
+            i: self.registers.i,
+            pc: self.registers.pc,
+            sp: self.registers.sp,
+            stack: self.stack,
+            dt: self.delay_timer.get(),
+            st: self.sound_timer.get(),
+        }
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn keypad(&self) -> [bool; 16] {
+        let keys = self.keypad.state();
+        std::array::from_fn(|key| keys[key] || self.touched[key])
+    }
+
+    fn is_key_pressed(&self, key: u8) -> bool {
+        self.keypad.is_pressed(key) || self.touched[key as usize]
+    }
+
+    pub fn input_driver_mut(&mut self) -> &mut I {
+        &mut self.input_driver
+    }
+
+    /**
+     * Polls input without executing anything, e.g. to keep the windows responsive while paused.
+     */
+    pub fn poll_input(&mut self) -> Result<(), Quit> {
+        self.handle_input();
+        if self.quit_requested {
+            return Err(Quit);
+        }
+        if self.reset_requested {
+            self.reset();
+        }
+        Ok(())
+    }
+
+    /**
+     * Handles the input events since the last poll and the keys held on the display's
+     * on-screen keypad. Returns the first key pressed.
+     */
+    fn handle_input(&mut self) -> Option<u8> {
+        let now = self.instructions;
+        let mut first_pressed = None;
+        for TimedEvent { at, event } in self.input_driver.poll(now) {
+            match event {
+                InputEvent::KeyDown(key) => {
+                    self.keypad.press(key, at);
+                    first_pressed = first_pressed.or(Some(key));
+                }
+                InputEvent::KeyUp(key) => self.keypad.release(key, at),
+                InputEvent::Hotkey(hotkey, pressed) => self.handle_hotkey(hotkey, pressed),
+                InputEvent::Quit => self.quit_requested = true,
+            }
+        }
+        self.keypad.update(now);
+
+        let mut touched = [false; 16];
+        for pointer in self.input_driver.pointers() {
+            if let Some(key) = self.display.touched_key(pointer) {
+                if !self.touched[key as usize] {
+                    first_pressed = first_pressed.or(Some(key));
+                }
+                touched[key as usize] = true;
+            }
+        }
+        self.touched = touched;
+        self.display.show_pressed(self.keypad());
+        first_pressed
+    }
+
+    fn save_screenshot(&self) {
+        if let Some(image) = self.display.screenshot() {
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs());
+            let path = format!("chip8-{}.bmp", seconds);
+            match fs::write(&path, image.to_bmp()) {
+                Ok(()) => eprintln!("saved screenshot to {}", path),
+                Err(e) => eprintln!("couldn't save screenshot to {}: {}", path, e),
             }
+        }
+    }
+
+    fn handle_hotkey(&mut self, hotkey: Hotkey, pressed: bool) {
+        match hotkey {
+            Hotkey::FastForward => self.fast_forward_held = pressed,
+            _ if !pressed => (),
+            Hotkey::Quit => self.quit_requested = true,
+            Hotkey::Reset => self.reset_requested = true,
+            Hotkey::Pause => self.paused = !self.paused,
+            Hotkey::FrameAdvance => self.frame_advance = self.paused,
+            Hotkey::ToggleTurbo => self.turbo = !self.turbo,
+            Hotkey::SaveSettings => self.save_settings(),
+            Hotkey::ToggleMute => self.sound_timer.toggle_mute(),
+            Hotkey::CyclePalette => self.display.cycle_palette(),
+            Hotkey::CycleFilter => self.display.cycle_filter(),
+            Hotkey::Screenshot => self.save_screenshot(),
+            Hotkey::TogglePhosphor => {
+                if let Some(effects) = self.display.effects_mut() {
+                    effects.phosphor = !effects.phosphor;
+                }
+            }
+            Hotkey::ToggleBlend => {
+                if let Some(effects) = self.display.effects_mut() {
+                    effects.blend = !effects.blend;
+                }
+            }
+            hotkey => {
+                if let Some(viewport) = self.display.viewport_mut() {
+                    match hotkey {
+                        Hotkey::ToggleGrid => viewport.grid = !viewport.grid,
+                        Hotkey::Rotate => viewport.rotate(),
+                        Hotkey::Flip => viewport.flip_horizontal = !viewport.flip_horizontal,
+                        Hotkey::ToggleScaling => viewport.toggle_scaling(),
+                        Hotkey::ToggleFullscreen => viewport.fullscreen = !viewport.fullscreen,
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * Shows what was drawn so far in the current frame, e.g. after stepping.
+     */
+    pub fn present(&mut self) {
+        self.display.present();
+    }
+
+    pub fn debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    /**
+     * Start counting executions per address and opcode, subroutine time and draws per frame.
+     */
+    pub fn enable_profiler(&mut self) {
+        self.profiler = Some(Profiler::new());
+    }
+
+    /**
+     * Keep an undo journal of the last `capacity` instructions for reverse stepping.
+     */
+    pub fn enable_journal(&mut self, capacity: usize) {
+        self.journal = Some(Journal::new(capacity));
+    }
+
+    /**
+     * The number of instructions that can currently be undone.
+     */
+    pub fn journal_len(&self) -> usize {
+        self.journal.as_ref().map_or(0, |journal| journal.len())
+    }
+
+    /**
+     * Undoes the last executed instruction. Returns false if there is nothing left to undo.
+     */
+    pub fn reverse_step(&mut self) -> bool {
+        self.undo().is_some()
+    }
 
-            // decrement timers 60 times per second.
-            if fc % 16 == 0 {
-                self.delay_timer.decrement();
-                self.sound_timer.decrement();
-                fc = 0;
+    /**
+     * Undoes instructions until reaching a breakpoint, or until undoing an instruction that
+     * triggered a watchpoint. Returns `None` if the start of the journal was reached first.
+     */
+    pub fn reverse_continue(&mut self) -> Option<Stop> {
+        while let Some(hits) = self.undo() {
+            if let Some(hit) = hits.into_iter().next() {
+                return Some(Stop::Watchpoint(hit));
+            }
+            let state = self.state();
+            if let Some(idx) = self.debugger.breakpoint_at(&state, &self.memory) {
+                return Some(Stop::Breakpoint(idx));
             }
-            fc += 1;
         }
+        None
     }
 
+    fn undo(&mut self) -> Option<Vec<WatchHit>> {
+        let entry = self.journal.as_mut()?.pop()?;
+        for (addr, old) in entry.memory.into_iter().rev() {
+            self.memory.value[addr as usize] = old;
+        }
+        match entry.pixels {
+            Some(PixelsUndo::Sprite(sprite, start_coord)) => {
+                self.display.draw_at(&sprite, start_coord);
+            }
+            Some(PixelsUndo::Frame(pixels)) => self.display.restore(*pixels),
+            None => (),
+        }
+        if let Some((idx, old)) = entry.stack {
+            self.stack[idx] = old;
+        }
+        self.registers.v = entry.v;
+        self.registers.i = entry.i;
+        self.registers.pc = entry.pc;
+        self.registers.sp = entry.sp;
+        self.delay_timer.set(entry.dt);
+        self.sound_timer.set(entry.st);
+        self.frame_progress = entry.frame_progress;
+        Some(entry.hits)
+    }
+
+    pub fn profile_report(&self) -> Option<String> {
+        self.profiler
+            .as_ref()
+            .map(|profiler| profiler.report(&self.memory))
+    }
+
+    pub fn execute_program(&mut self) {
+        loop {
+            let result = if self.paused && !self.frame_advance {
+                std::thread::sleep(Duration::from_millis(10));
+                self.display.present();
+                self.poll_input().map(|_| None)
+            } else {
+                self.frame_advance = false;
+                self.run_frame()
+            };
+            match result {
+                Ok(Some(_)) => self.debugger.resume(),
+                Ok(None) => (),
+                Err(_) => return,
+            }
+        }
+    }
+
+    /**
+     * Runs until the end of the current frame or until the debugger stops execution.
+     */
+    pub fn run_frame(&mut self) -> Result<Option<Stop>, Quit> {
+        loop {
+            let stop = self.run_instruction()?;
+            if stop.is_some() || self.frame_progress == 0 {
+                return Ok(stop);
+            }
+        }
+    }
+
+    /**
+     * Executes a single instruction unless the debugger stops before it. Timers are
+     * decremented once a frame's worth of instructions has been executed.
+     */
+    pub fn run_instruction(&mut self) -> Result<Option<Stop>, Quit> {
+        self.poll_input()?;
+
+        if let Some(stop) = self.check_before() {
+            return Ok(Some(stop));
+        }
+
+        // running faster only skips sleeping, so sleep once every `speed` instructions
+        if self.throttled && self.instructions.is_multiple_of(self.speed() as u64) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        self.instructions += 1;
+
+        let stop = self.step();
+        self.frame_progress += 1;
+        if self.frame_progress >= self.instructions_per_frame {
+            self.frame_progress = 0;
+            self.delay_timer.decrement();
+            self.sound_timer.decrement();
+            self.display.end_frame();
+            self.display.present();
+            if let Some(profiler) = &mut self.profiler {
+                profiler.end_frame();
+            }
+        }
+        Ok(stop)
+    }
+
+    fn check_before(&mut self) -> Option<Stop> {
+        let opcode = self.memory.read_instruction(self.registers.pc);
+        let upcoming = match Opcodes::from_bytes(opcode) {
+            None => Some(DebugEvent::InvalidOpcode),
+            Some(Opcodes::LDK(_)) => Some(DebugEvent::KeyWait),
+            Some(_) => None,
+        };
+        let state = self.state();
+        self.debugger.check_before(&state, &self.memory, upcoming)
+    }
+
+    fn step(&mut self) -> Option<Stop> {
+        self.debugger.begin_step();
+        if self.journal.is_some() {
+            self.journal_entry = Some(JournalEntry {
+                v: self.registers.v,
+                i: self.registers.i,
+                pc: self.registers.pc,
+                sp: self.registers.sp,
+                dt: self.delay_timer.get(),
+                st: self.sound_timer.get(),
+                frame_progress: self.frame_progress,
+                stack: None,
+                memory: Vec::new(),
+                pixels: None,
+                hits: Vec::new(),
+            });
+        }
+        let pc = self.registers.pc;
+        let opcode = self.memory.read_instruction(pc);
+        for addr in [pc, pc + 1] {
+            let value = self.memory.value[addr as usize];
+            self.debugger
+                .check_access(pc, addr, Access::Read, value, value);
+        }
+        if let Some(profiler) = &mut self.profiler {
+            // as decoded, which e.g. runs any 0x0NNE as RET
+            let flow = match Opcodes::from_bytes(opcode) {
+                Some(Opcodes::CALL(Addr(addr))) => Flow::Call(addr),
+                Some(Opcodes::RET) => Flow::Return,
+                Some(Opcodes::DRW(..)) => Flow::Draw,
+                _ => Flow::Other,
+            };
+            profiler.record(pc, opcode, flow);
+        }
+        match self.execute_opcode(opcode) {
+            ProgramCounter::Next => self.registers.pc += OPCODE_SIZE,
+            ProgramCounter::Skip(true) => self.registers.pc += 2 * OPCODE_SIZE,
+            ProgramCounter::Skip(false) => self.registers.pc += OPCODE_SIZE,
+            ProgramCounter::Jump(addr) => self.registers.pc = addr as u16,
+        }
+        if let (Some(journal), Some(mut entry)) = (&mut self.journal, self.journal_entry.take()) {
+            entry.hits = self.debugger.hits().to_vec();
+            journal.push(entry);
+        }
+        self.debugger.check_after()
+    }
+
+    fn read_memory(&mut self, addr: u16) -> u8 {
+        let value = self.memory.value[addr as usize];
+        self.debugger
+            .check_access(self.registers.pc, addr, Access::Read, value, value);
+        value
+    }
+
+    fn write_memory(&mut self, addr: u16, value: u8) {
+        let old = self.memory.value[addr as usize];
+        self.debugger
+            .check_access(self.registers.pc, addr, Access::Write, old, value);
+        if let Some(entry) = &mut self.journal_entry {
+            entry.memory.push((addr, old));
+        }
+        self.memory.value[addr as usize] = value;
+    }
+
+    fn shift_vy_quirk(&mut self, x: usize, y: usize) {
+        if self.quirks.shift {
+            self.registers.v[x] = self.registers.v[y];
+        }
+    }
+
+    fn reset_vf_quirk(&mut self) {
+        if self.quirks.vf_reset {
+            self.registers.set_vf(0);
+        }
+    }
+
+    /**
+     * Waits for a key to be pressed, returning `None` if quitting or resetting instead.
+     */
     fn loop_until_keypressed(&mut self) -> Option<u8> {
-        let result;
-        'until: loop {
+        if !self.input_driver.is_interactive() {
+            self.quit_requested = true;
+            return None;
+        }
+        loop {
             std::thread::sleep(Duration::from_millis(10));
 
-            match self.input_driver.poll() {
-                Ok(Keypad {
-                    keypad,
-                    last_pressed: lp @ Some(_),
-                }) => {
-                    result = lp;
-                    self.keypad = keypad;
-                    break 'until;
-                }
-                Err(_) => panic!("interuppted"),
-                _ => (),
+            // keep showing what was drawn before waiting
+            self.display.present();
+            let pressed = self.handle_input();
+            // give up waiting, the next poll quits or resets
+            if self.quit_requested || self.reset_requested {
+                return None;
+            }
+            if pressed.is_some() {
+                return pressed;
             }
         }
-        result
     }
 
     fn execute_opcode(&mut self, raw_opcode: u16) -> ProgramCounter {
@@ -115,17 +650,26 @@ where
         );
         match opcode {
             Opcodes::CLS => {
+                if let Some(entry) = &mut self.journal_entry {
+                    entry.pixels = Some(PixelsUndo::Frame(Box::new(self.display.pixels().clone())));
+                }
                 self.display.clear();
+                self.debugger.record_event(DebugEvent::Clear);
                 ProgramCounter::Next
             }
             Opcodes::RET => {
                 self.registers.sp -= 1;
+                self.debugger.record_event(DebugEvent::StackDepth);
                 ProgramCounter::Jump(self.stack[self.registers.sp] as usize)
             }
             Opcodes::JP(Addr(value)) => ProgramCounter::Jump(value as usize),
             Opcodes::CALL(Addr(value)) => {
+                if let Some(entry) = &mut self.journal_entry {
+                    entry.stack = Some((self.registers.sp, self.stack[self.registers.sp]));
+                }
                 self.stack[self.registers.sp] = self.registers.pc + OPCODE_SIZE;
                 self.registers.sp += 1;
+                self.debugger.record_event(DebugEvent::StackDepth);
                 ProgramCounter::Jump(value as usize)
             }
             Opcodes::SEByte(RegisterN(x), Byte(value)) => {
@@ -154,14 +698,17 @@ where
             }
             Opcodes::OR(RegisterN(x), RegisterN(y)) => {
                 self.registers.v[x] |= self.registers.v[y];
+                self.reset_vf_quirk();
                 ProgramCounter::Next
             }
             Opcodes::ANDReg(RegisterN(x), RegisterN(y)) => {
                 self.registers.v[x] &= self.registers.v[y];
+                self.reset_vf_quirk();
                 ProgramCounter::Next
             }
             Opcodes::XOR(RegisterN(x), RegisterN(y)) => {
                 self.registers.v[x] ^= self.registers.v[y];
+                self.reset_vf_quirk();
                 ProgramCounter::Next
             }
             Opcodes::ADDReg(RegisterN(x), RegisterN(y)) => {
@@ -187,7 +734,8 @@ where
                 self.registers.v[x] = vx.wrapping_sub(vy);
                 ProgramCounter::Next
             }
-            Opcodes::SHR(RegisterN(x)) => {
+            Opcodes::SHR(RegisterN(x), RegisterN(y)) => {
+                self.shift_vy_quirk(x, y);
                 let vx = self.registers.v[x];
                 self.registers.set_vf(vx & 1);
                 self.registers.v[x] /= 2;
@@ -204,7 +752,8 @@ where
                 self.registers.v[x] = vy.wrapping_sub(vx);
                 ProgramCounter::Next
             }
-            Opcodes::SHL(RegisterN(x)) => {
+            Opcodes::SHL(RegisterN(x), RegisterN(y)) => {
+                self.shift_vy_quirk(x, y);
                 let vx = self.registers.v[x];
                 if vx >= 128 {
                     self.registers.set_vf(1)
@@ -224,43 +773,63 @@ where
                 ProgramCounter::Next
             }
             Opcodes::JPV0(Addr(v)) => {
-                let addr = v + self.registers.v[0] as u16;
+                // BXNN with the jump quirk
+                let offset = if self.quirks.jump { v as usize >> 8 } else { 0 };
+                let addr = v + self.registers.v[offset] as u16;
                 ProgramCounter::Jump(addr as usize)
             }
             Opcodes::RND(RegisterN(x), Byte(kk)) => {
-                let mut rng = rand::thread_rng();
-                self.registers.v[x] = rng.gen::<u8>() & kk;
+                self.registers.v[x] = self.rng.gen::<u8>() & kk;
                 ProgramCounter::Next
             }
             Opcodes::DRW(RegisterN(x), RegisterN(y), Nibble(n)) => {
-                let addr = self.registers.i as usize;
-                let sprite = &self.memory.value[addr..addr + n as usize];
-                let start_coord = (self.registers.v[x] as usize, self.registers.v[y] as usize);
-                let collided = self.display.draw_at(sprite, start_coord);
+                let addr = self.registers.i;
+                let mut sprite: Vec<u8> = (addr..addr + n as u16)
+                    .map(|addr| self.read_memory(addr))
+                    .collect();
+                let mut start_coord = (self.registers.v[x] as usize, self.registers.v[y] as usize);
+                if self.quirks.clip {
+                    start_coord = (start_coord.0 % GRID_WIDTH, start_coord.1 % GRID_HEIGHT);
+                    clip_sprite(&mut sprite, start_coord);
+                }
+                let collided = self.display.draw_at(&sprite, start_coord);
+                if collided {
+                    self.debugger.record_event(DebugEvent::Collision);
+                }
+                if let Some(entry) = &mut self.journal_entry {
+                    entry.pixels = Some(PixelsUndo::Sprite(sprite, start_coord));
+                }
                 self.registers.set_vf(if collided { 1 } else { 0 });
                 ProgramCounter::Next
             }
             Opcodes::SKP(RegisterN(x)) => {
-                ProgramCounter::Skip(self.keypad[self.registers.v[x] as usize])
+                self.display.key_tested(self.registers.v[x]);
+                ProgramCounter::Skip(self.is_key_pressed(self.registers.v[x]))
             }
             Opcodes::SKNP(RegisterN(x)) => {
-                ProgramCounter::Skip(!self.keypad[self.registers.v[x] as usize])
+                self.display.key_tested(self.registers.v[x]);
+                ProgramCounter::Skip(!self.is_key_pressed(self.registers.v[x]))
             }
             Opcodes::LDVXWITHDT(RegisterN(x)) => {
                 self.registers.v[x] = self.delay_timer.get();
                 ProgramCounter::Next
             }
-            Opcodes::LDK(RegisterN(x)) => {
-                if let Some(keycode) = self.loop_until_keypressed() {
+            Opcodes::LDK(RegisterN(x)) => match self.loop_until_keypressed() {
+                Some(keycode) => {
                     self.registers.v[x] = keycode;
+                    ProgramCounter::Next
                 }
-                ProgramCounter::Next
-            }
+                // still waiting when quitting
+                None => ProgramCounter::Jump(self.registers.pc as usize),
+            },
             Opcodes::LDDTWITHVX(RegisterN(x)) => {
                 self.delay_timer.set(self.registers.v[x]);
                 ProgramCounter::Next
             }
             Opcodes::LDST(RegisterN(x)) => {
+                if self.sound_timer.get() == 0 && self.registers.v[x] > 0 {
+                    self.debugger.record_event(DebugEvent::SoundStart);
+                }
                 self.sound_timer.set(self.registers.v[x]);
                 ProgramCounter::Next
             }
@@ -271,35 +840,38 @@ where
                 ProgramCounter::Next
             }
             Opcodes::LDSPRITE(RegisterN(x)) => {
-                if let Some(mem_loc) = Memory::query_hex_location(self.registers.v[x]) {
-                    self.registers.i = mem_loc as u16;
+                if let Some(mem_loc) = self.memory.query_hex_location(self.registers.v[x]) {
+                    self.registers.i = mem_loc;
                 }
                 ProgramCounter::Next
             }
             Opcodes::LDBCD(RegisterN(x)) => {
                 let get_digit = |n: u32, d: u32| (n / u32::pow(10, d) % 10);
                 let vx = self.registers.v[x] as u32;
-                let mem_loc = self.registers.i as usize;
-                self.memory.value[mem_loc] = get_digit(vx, 2) as u8;
-                self.memory.value[mem_loc + 1] = get_digit(vx, 1) as u8;
-                self.memory.value[mem_loc + 2] = get_digit(vx, 0) as u8;
+                let mem_loc = self.registers.i;
+                self.write_memory(mem_loc, get_digit(vx, 2) as u8);
+                self.write_memory(mem_loc + 1, get_digit(vx, 1) as u8);
+                self.write_memory(mem_loc + 2, get_digit(vx, 0) as u8);
                 ProgramCounter::Next
             }
             Opcodes::LDTHROUGH(RegisterN(x)) => {
                 let mem_loc_start = self.registers.i;
-                self.registers.v[0..=x as usize]
-                    .iter()
-                    .zip(mem_loc_start..)
-                    .for_each(|(value, mem_loc)| {
-                        self.memory.value[mem_loc as usize] = *value;
-                    });
+                for (n, mem_loc) in (0..=x).zip(mem_loc_start..) {
+                    self.write_memory(mem_loc, self.registers.v[n]);
+                }
+                if self.quirks.load_store {
+                    self.registers.i += x as u16 + 1;
+                }
                 ProgramCounter::Next
             }
             Opcodes::LDTHROUGHINTOI(RegisterN(x)) => {
                 let indices = (0..=x).zip(self.registers.i..);
                 for (n, i) in indices {
-                    let mem_cell = self.memory.value[i as usize];
-                    self.registers.v[n as usize] = mem_cell;
+                    let mem_cell = self.read_memory(i);
+                    self.registers.v[n] = mem_cell;
+                }
+                if self.quirks.load_store {
+                    self.registers.i += x as u16 + 1;
                 }
                 ProgramCounter::Next
             }
@@ -307,6 +879,40 @@ where
     }
 }
 
+/**
+ * Cuts off the rows and columns of `sprite` that would wrap around the screen when drawn
+ * at `(x, y)`.
+ */
+fn clip_sprite(sprite: &mut Vec<u8>, (x, y): (usize, usize)) {
+    sprite.truncate(GRID_HEIGHT - y);
+    if x + 8 > GRID_WIDTH {
+        let mask = 0xffu8 << (x + 8 - GRID_WIDTH);
+        for row in sprite.iter_mut() {
+            *row &= mask;
+        }
+    }
+}
+
+/**
+ * The input driver asked to stop emulating, e.g. because the window was closed.
+ */
+#[derive(PartialEq, Debug)]
+pub struct Quit;
+
+/**
+ * A copy of the registers, stack and timers, e.g. for evaluating breakpoint conditions.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct MachineState {
+    pub v: [u8; 16],
+    pub i: u16,
+    pub pc: u16,
+    pub sp: usize,
+    pub stack: [u16; 16],
+    pub dt: u8,
+    pub st: u8,
+}
+
 struct Registers {
     v: [u8; 16],
     i: u16,
@@ -315,13 +921,13 @@ struct Registers {
 }
 
 impl Registers {
-    fn new() -> Registers {
+    fn new(pc: u16) -> Registers {
         // TODO not sure that these are "good" init values for registers
         Registers {
             // registers V0-VF
             v: [0; 16],
             i: STARTING_MEMORY,
-            pc: STARTING_MEMORY,
+            pc,
             sp: 0,
         }
     }
@@ -356,9 +962,9 @@ enum Opcodes {
     XOR(RegisterN, RegisterN),
     ADDReg(RegisterN, RegisterN),
     SUB(RegisterN, RegisterN),
-    SHR(RegisterN),
+    SHR(RegisterN, RegisterN),
     SUBN(RegisterN, RegisterN),
-    SHL(RegisterN),
+    SHL(RegisterN, RegisterN),
     SNEReg(RegisterN, RegisterN),
     LDI(Addr),
     JPV0(Addr),
@@ -395,9 +1001,9 @@ impl Opcodes {
             (8, x, y, 3) => Some(Opcodes::XOR(RegisterN(x), RegisterN(y))),
             (8, x, y, 4) => Some(Opcodes::ADDReg(RegisterN(x), RegisterN(y))),
             (8, x, y, 5) => Some(Opcodes::SUB(RegisterN(x), RegisterN(y))),
-            (8, x, _, 6) => Some(Opcodes::SHR(RegisterN(x))),
+            (8, x, y, 6) => Some(Opcodes::SHR(RegisterN(x), RegisterN(y))),
             (8, x, y, 7) => Some(Opcodes::SUBN(RegisterN(x), RegisterN(y))),
-            (8, x, _, 0xE) => Some(Opcodes::SHL(RegisterN(x))),
+            (8, x, y, 0xE) => Some(Opcodes::SHL(RegisterN(x), RegisterN(y))),
             (9, x, y, 0) => Some(Opcodes::SNEReg(RegisterN(x), RegisterN(y))),
             (0xA, _, _, _) => Some(Opcodes::LDI(get_addr(raw_opcode))),
             (0xB, _, _, _) => Some(Opcodes::JPV0(get_addr(raw_opcode))),
@@ -419,6 +1025,110 @@ impl Opcodes {
     }
 }
 
+impl Opcodes {
+    fn name(&self) -> &'static str {
+        match self {
+            Opcodes::CLS => "CLS",
+            Opcodes::RET => "RET",
+            Opcodes::JP(_) => "JP",
+            Opcodes::CALL(_) => "CALL",
+            Opcodes::SEByte(..) => "SEByte",
+            Opcodes::SNE(..) => "SNE",
+            Opcodes::SEReg(..) => "SEReg",
+            Opcodes::LDByte(..) => "LDByte",
+            Opcodes::ADDByte(..) => "ADDByte",
+            Opcodes::LDReg(..) => "LDReg",
+            Opcodes::OR(..) => "OR",
+            Opcodes::ANDReg(..) => "ANDReg",
+            Opcodes::XOR(..) => "XOR",
+            Opcodes::ADDReg(..) => "ADDReg",
+            Opcodes::SUB(..) => "SUB",
+            Opcodes::SHR(..) => "SHR",
+            Opcodes::SUBN(..) => "SUBN",
+            Opcodes::SHL(..) => "SHL",
+            Opcodes::SNEReg(..) => "SNEReg",
+            Opcodes::LDI(_) => "LDI",
+            Opcodes::JPV0(_) => "JPV0",
+            Opcodes::RND(..) => "RND",
+            Opcodes::DRW(..) => "DRW",
+            Opcodes::SKP(_) => "SKP",
+            Opcodes::SKNP(_) => "SKNP",
+            Opcodes::LDVXWITHDT(_) => "LDVXWITHDT",
+            Opcodes::LDK(_) => "LDK",
+            Opcodes::LDDTWITHVX(_) => "LDDTWITHVX",
+            Opcodes::LDST(_) => "LDST",
+            Opcodes::ADDI(_) => "ADDI",
+            Opcodes::LDSPRITE(_) => "LDSPRITE",
+            Opcodes::LDBCD(_) => "LDBCD",
+            Opcodes::LDTHROUGH(_) => "LDTHROUGH",
+            Opcodes::LDTHROUGHINTOI(_) => "LDTHROUGHINTOI",
+        }
+    }
+}
+
+/**
+ * Mnemonics follow Cowgod's specification, e.g. `LD V1, 0x22` or `DRW V0, V1, 0x5`.
+ */
+impl fmt::Display for Opcodes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Opcodes::CLS => write!(f, "CLS"),
+            Opcodes::RET => write!(f, "RET"),
+            Opcodes::JP(Addr(a)) => write!(f, "JP {:#05x}", a),
+            Opcodes::CALL(Addr(a)) => write!(f, "CALL {:#05x}", a),
+            Opcodes::SEByte(RegisterN(x), Byte(b)) => write!(f, "SE V{:X}, {:#04x}", x, b),
+            Opcodes::SNE(RegisterN(x), Byte(b)) => write!(f, "SNE V{:X}, {:#04x}", x, b),
+            Opcodes::SEReg(RegisterN(x), RegisterN(y)) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Opcodes::LDByte(RegisterN(x), Byte(b)) => write!(f, "LD V{:X}, {:#04x}", x, b),
+            Opcodes::ADDByte(RegisterN(x), Byte(b)) => write!(f, "ADD V{:X}, {:#04x}", x, b),
+            Opcodes::LDReg(RegisterN(x), RegisterN(y)) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Opcodes::OR(RegisterN(x), RegisterN(y)) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Opcodes::ANDReg(RegisterN(x), RegisterN(y)) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Opcodes::XOR(RegisterN(x), RegisterN(y)) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Opcodes::ADDReg(RegisterN(x), RegisterN(y)) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Opcodes::SUB(RegisterN(x), RegisterN(y)) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Opcodes::SHR(RegisterN(x), _) => write!(f, "SHR V{:X}", x),
+            Opcodes::SUBN(RegisterN(x), RegisterN(y)) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Opcodes::SHL(RegisterN(x), _) => write!(f, "SHL V{:X}", x),
+            Opcodes::SNEReg(RegisterN(x), RegisterN(y)) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Opcodes::LDI(Addr(a)) => write!(f, "LD I, {:#05x}", a),
+            Opcodes::JPV0(Addr(a)) => write!(f, "JP V0, {:#05x}", a),
+            Opcodes::RND(RegisterN(x), Byte(b)) => write!(f, "RND V{:X}, {:#04x}", x, b),
+            Opcodes::DRW(RegisterN(x), RegisterN(y), Nibble(n)) => {
+                write!(f, "DRW V{:X}, V{:X}, {:#03x}", x, y, n)
+            }
+            Opcodes::SKP(RegisterN(x)) => write!(f, "SKP V{:X}", x),
+            Opcodes::SKNP(RegisterN(x)) => write!(f, "SKNP V{:X}", x),
+            Opcodes::LDVXWITHDT(RegisterN(x)) => write!(f, "LD V{:X}, DT", x),
+            Opcodes::LDK(RegisterN(x)) => write!(f, "LD V{:X}, K", x),
+            Opcodes::LDDTWITHVX(RegisterN(x)) => write!(f, "LD DT, V{:X}", x),
+            Opcodes::LDST(RegisterN(x)) => write!(f, "LD ST, V{:X}", x),
+            Opcodes::ADDI(RegisterN(x)) => write!(f, "ADD I, V{:X}", x),
+            Opcodes::LDSPRITE(RegisterN(x)) => write!(f, "LD F, V{:X}", x),
+            Opcodes::LDBCD(RegisterN(x)) => write!(f, "LD B, V{:X}", x),
+            Opcodes::LDTHROUGH(RegisterN(x)) => write!(f, "LD [I], V{:X}", x),
+            Opcodes::LDTHROUGHINTOI(RegisterN(x)) => write!(f, "LD V{:X}, [I]", x),
+        }
+    }
+}
+
+/**
+ * Disassembles a raw opcode, falling back to a `DW` data word for bytes that are not an instruction.
+ */
+pub fn disassemble(raw_opcode: u16) -> String {
+    match Opcodes::from_bytes(raw_opcode) {
+        Some(opcode) => opcode.to_string(),
+        None => format!("DW {:#06x}", raw_opcode),
+    }
+}
+
+/**
+ * The opcode kind, e.g. `DRW` or `LDByte`, or `None` if `raw_opcode` is not an instruction.
+ */
+pub fn opcode_name(raw_opcode: u16) -> Option<&'static str> {
+    Opcodes::from_bytes(raw_opcode).map(|opcode| opcode.name())
+}
+
 fn get_addr(raw_opcode: u16) -> Addr {
     Addr(raw_opcode & 0x0FFF)
 }
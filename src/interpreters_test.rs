@@ -2,9 +2,12 @@ mod tests {
     use mocks::*;
 
     use crate::{
+        debuggers::{Access, Breakpoint, DebugEvent, Stop, WatchHit, Watchpoint},
         hexadecimal_sprites::SEVEN,
+        input_driver::InputEvent,
         interpreters::{Interpreter, ProgramCounter},
         memory::Memory,
+        quirks::Quirks,
     };
     fn make_interpreter() -> Interpreter<TestDisplay, TestInput, TestTimer, TestTimer> {
         let mem = Memory::empty();
@@ -257,24 +260,340 @@ mod tests {
         assert_eq!(interpreter.registers.v[2], 12);
         assert_eq!(interpreter.registers.v[3], 13);
     }
-    // TODO test keyboard instructions
-    mod mocks {
-        use sdl2::{event::Event, keyboard::Keycode};
+    #[test]
+    fn test_shift_quirk() {
+        let mut interpreter = make_interpreter();
+        interpreter.registers.v[1] = 0x10;
+        interpreter.registers.v[2] = 0x03;
+        interpreter.execute_opcode(0x8126);
+        assert_eq!(interpreter.registers.v[1], 0x08);
+
+        interpreter.set_quirks(Quirks {
+            shift: true,
+            ..Quirks::default()
+        });
+        interpreter.execute_opcode(0x8126);
+        assert_eq!(interpreter.registers.v[1], 0x01);
+        assert_eq!(interpreter.registers.v[0xf], 1);
+    }
+    #[test]
+    fn test_load_store_quirk() {
+        let mut interpreter = make_interpreter();
+        interpreter.set_quirks(Quirks {
+            load_store: true,
+            ..Quirks::default()
+        });
+        interpreter.registers.i = 0x200;
+        interpreter.execute_opcode(0xf255);
+        assert_eq!(interpreter.registers.i, 0x203);
+        interpreter.execute_opcode(0xf165);
+        assert_eq!(interpreter.registers.i, 0x205);
+    }
+    #[test]
+    fn test_drw_clip_quirk() {
+        let mut interpreter = make_interpreter();
+        interpreter.set_quirks(Quirks {
+            clip: true,
+            ..Quirks::default()
+        });
+        interpreter.registers.i = 0x200;
+        interpreter.memory.value[0x200] = 0xff;
+        interpreter.registers.v[1] = 62;
+        interpreter.registers.v[2] = 0;
+        interpreter.execute_opcode(0xD121);
+
+        let row = &interpreter.display.pixels.value[0];
+        assert_eq!(&row[62..], &[1, 1]);
+        assert_eq!(&row[0..6], &[0; 6]);
+    }
+    #[test]
+    fn test_profiler_report() {
+        let mut interpreter = make_interpreter();
+        interpreter.enable_profiler();
+        // CALL 0x300 followed by RET
+        interpreter.memory.value[0x200] = 0x23;
+        interpreter.memory.value[0x201] = 0x00;
+        interpreter.memory.value[0x300] = 0x00;
+        interpreter.memory.value[0x301] = 0xEE;
+
+        interpreter.step();
+        interpreter.step();
+
+        assert_eq!(interpreter.registers.pc, 0x202);
+        let report = interpreter.profile_report().unwrap();
+        assert!(report.contains("2 instructions executed"));
+        assert!(report.contains("CALL 0x300"));
+        assert!(report.contains("RET"));
+    }
+    #[test]
+    fn test_profiler_counts_any_0nne_as_ret() {
+        let mut interpreter = make_interpreter();
+        interpreter.enable_profiler();
+        // CALL 0x300, which returns with 0x01EE, then CALL 0x400, which returns with RET
+        interpreter.memory.value[0x200] = 0x23;
+        interpreter.memory.value[0x201] = 0x00;
+        interpreter.memory.value[0x202] = 0x24;
+        interpreter.memory.value[0x203] = 0x00;
+        interpreter.memory.value[0x300] = 0x01;
+        interpreter.memory.value[0x301] = 0xEE;
+        interpreter.memory.value[0x400] = 0x00;
+        interpreter.memory.value[0x401] = 0xEE;
+
+        for _ in 0..4 {
+            interpreter.step();
+        }
+
+        assert_eq!(interpreter.registers.pc, 0x204);
+        let report = interpreter.profile_report().unwrap();
+        for addr in [0x300, 0x400] {
+            let line = format!("  {:#05x} {:>7} {:>13} ", addr, 1, 1);
+            assert!(report.contains(&line), "{}", report);
+        }
+    }
+    #[test]
+    fn test_write_watchpoint() {
+        let opcode = 0xf633;
+        let mut interpreter = make_interpreter();
+        interpreter
+            .debugger_mut()
+            .add_watchpoint(Watchpoint::parse("0x301-0x3ff:w").unwrap());
+        interpreter.registers.pc = 0x210;
+        interpreter.registers.v[6] = 253;
+        interpreter.registers.i = 0x300;
+        interpreter.memory.value[0x301] = 9;
+
+        interpreter.execute_opcode(opcode);
+
+        let hits = interpreter.debugger().hits();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(
+            hits[0],
+            WatchHit {
+                pc: 0x210,
+                addr: 0x301,
+                access: Access::Write,
+                old: 9,
+                new: 5,
+            }
+        );
+    }
+    #[test]
+    fn test_conditional_breakpoint() {
+        let mut interpreter = make_interpreter();
+        // ADD V3, 0x08 in a loop: JP 0x200
+        interpreter.memory.value[0x200] = 0x73;
+        interpreter.memory.value[0x201] = 0x08;
+        interpreter.memory.value[0x202] = 0x12;
+        interpreter.memory.value[0x203] = 0x00;
+        interpreter
+            .debugger_mut()
+            .add_breakpoint(Breakpoint::parse("0x200 after 2 if V3 >= 0x08").unwrap());
+
+        let mut stop = None;
+        for _ in 0..10 {
+            stop = interpreter.run_instruction().unwrap();
+            if stop.is_some() {
+                break;
+            }
+        }
+
+        assert_eq!(stop, Some(Stop::Breakpoint(0)));
+        assert_eq!(interpreter.registers.v[3], 0x10);
+        assert_eq!(interpreter.registers.pc, 0x200);
+        // resuming executes the instruction we stopped at
+        interpreter.debugger_mut().resume();
+        assert_eq!(interpreter.run_instruction().unwrap(), None);
+        assert_eq!(interpreter.registers.v[3], 0x18);
+    }
+    #[test]
+    fn test_breakpoint_after_watchpoint() {
+        let mut interpreter = make_interpreter();
+        // LD I, 0x300; LD [I], V0; ADD V1, 0x01
+        let program = [0xA3, 0x00, 0xF0, 0x55, 0x71, 0x01];
+        interpreter.memory.value[0x200..0x200 + program.len()].copy_from_slice(&program);
+        interpreter
+            .debugger_mut()
+            .add_watchpoint(Watchpoint::parse("0x300:w").unwrap());
+        interpreter
+            .debugger_mut()
+            .add_breakpoint(Breakpoint::parse("0x204").unwrap());
+
+        assert_eq!(interpreter.run_instruction().unwrap(), None);
+        let stop = interpreter.run_instruction().unwrap();
+        assert!(matches!(stop, Some(Stop::Watchpoint(_))));
+        assert_eq!(interpreter.registers.pc, 0x204);
+        // the instruction after the watchpoint hit has not been checked yet
+        interpreter.debugger_mut().resume();
+        assert_eq!(
+            interpreter.run_instruction().unwrap(),
+            Some(Stop::Breakpoint(0))
+        );
+        assert_eq!(interpreter.registers.v[1], 0);
+        interpreter.debugger_mut().resume();
+        assert_eq!(interpreter.run_instruction().unwrap(), None);
+        assert_eq!(interpreter.registers.v[1], 1);
+    }
+    #[test]
+    fn test_frames_end_with_the_emulated_frame() {
+        let mut interpreter = make_interpreter();
+        interpreter.set_instructions_per_frame(2);
+        // JP 0x200
+        interpreter.memory.value[0x200] = 0x12;
+        interpreter.memory.value[0x201] = 0x00;
+
+        for _ in 0..5 {
+            interpreter.run_instruction().unwrap();
+            // presenting outside the frame boundary, e.g. in a debugger
+            interpreter.present();
+        }
 
+        assert_eq!(interpreter.display.frames_ended, 2);
+    }
+    #[test]
+    fn test_event_breakpoint() {
+        let mut interpreter = make_interpreter();
+        interpreter
+            .debugger_mut()
+            .add_event_breakpoint(DebugEvent::InvalidOpcode);
+        interpreter
+            .debugger_mut()
+            .add_event_breakpoint(DebugEvent::StackDepth);
+        // CALL 0x300, then an invalid opcode
+        interpreter.memory.value[0x200] = 0x23;
+        interpreter.memory.value[0x201] = 0x00;
+        interpreter.memory.value[0x300] = 0xFF;
+        interpreter.memory.value[0x301] = 0xFF;
+
+        let stop = interpreter.run_instruction().unwrap();
+        assert_eq!(stop, Some(Stop::Event(DebugEvent::StackDepth)));
+        let stop = interpreter.run_instruction().unwrap();
+        assert_eq!(stop, Some(Stop::Event(DebugEvent::InvalidOpcode)));
+        assert_eq!(interpreter.registers.pc, 0x300);
+    }
+    #[test]
+    fn test_reverse_step() {
+        let mut interpreter = make_interpreter();
+        interpreter.enable_journal(16);
+        // LD V1, 0xFE; LD I, 0x300; LD B, V1; DRW V0, V0, 0x3; CLS
+        let program = [0x61, 0xFE, 0xA3, 0x00, 0xF1, 0x33, 0xD0, 0x03, 0x00, 0xE0];
+        interpreter.memory.value[0x200..0x200 + program.len()].copy_from_slice(&program);
+        for _ in 0..4 {
+            interpreter.step();
+        }
+        let drawn = interpreter.display.pixels.value;
+        interpreter.step();
+
+        assert!(interpreter.reverse_step());
+        assert_eq!(interpreter.display.pixels.value, drawn);
+        assert!(interpreter.reverse_step());
+        assert_eq!(interpreter.display.pixels.value, [[0; 64]; 32]);
+        assert!(interpreter.reverse_step());
+        assert_eq!(interpreter.memory.value[0x300..0x303], [0, 0, 0]);
+        assert_eq!(interpreter.registers.pc, 0x204);
+        assert!(interpreter.reverse_step());
+        assert!(interpreter.reverse_step());
+        assert_eq!(interpreter.registers.v[1], 0);
+        assert_eq!(interpreter.registers.pc, 0x200);
+        assert!(!interpreter.reverse_step());
+    }
+    #[test]
+    fn test_reverse_continue_to_watchpoint() {
+        let mut interpreter = make_interpreter();
+        interpreter.enable_journal(16);
+        interpreter
+            .debugger_mut()
+            .add_watchpoint(Watchpoint::parse("0x300:w").unwrap());
+        // LD I, 0x300; LD [I], V0; ADD V0, 0x01; ADD V0, 0x01
+        let program = [0xA3, 0x00, 0xF0, 0x55, 0x70, 0x01, 0x70, 0x01];
+        interpreter.memory.value[0x200..0x200 + program.len()].copy_from_slice(&program);
+        for _ in 0..4 {
+            interpreter.step();
+        }
+
+        let stop = interpreter.reverse_continue();
+
+        assert!(matches!(
+            stop,
+            Some(Stop::Watchpoint(WatchHit { addr: 0x300, .. }))
+        ));
+        assert_eq!(interpreter.registers.pc, 0x202);
+        assert_eq!(interpreter.registers.v[0], 0);
+    }
+    #[test]
+    fn test_skp_sees_held_key() {
+        let mut interpreter = make_interpreter();
+        interpreter.registers.v[2] = 0xA;
+        interpreter.input_driver.events = vec![InputEvent::KeyDown(0xA)];
+        interpreter.poll_input().unwrap();
+        assert_eq!(
+            interpreter.execute_opcode(0xE29E),
+            ProgramCounter::Skip(true)
+        );
+        assert_eq!(
+            interpreter.execute_opcode(0xE2A1),
+            ProgramCounter::Skip(false)
+        );
+
+        interpreter.input_driver.events = vec![InputEvent::KeyUp(0xA)];
+        interpreter.instructions += 100;
+        interpreter.poll_input().unwrap();
+        assert_eq!(
+            interpreter.execute_opcode(0xE29E),
+            ProgramCounter::Skip(false)
+        );
+    }
+    #[test]
+    fn test_quick_tap_is_not_lost() {
+        let mut interpreter = make_interpreter();
+        interpreter.registers.v[0] = 0x3;
+        interpreter.input_driver.events = vec![InputEvent::KeyDown(0x3), InputEvent::KeyUp(0x3)];
+        interpreter.poll_input().unwrap();
+        assert_eq!(
+            interpreter.execute_opcode(0xE09E),
+            ProgramCounter::Skip(true)
+        );
+
+        interpreter.instructions += 16;
+        interpreter.poll_input().unwrap();
+        assert_eq!(
+            interpreter.execute_opcode(0xE09E),
+            ProgramCounter::Skip(false)
+        );
+    }
+    #[test]
+    fn test_ldk_gets_the_pressed_key() {
+        let mut interpreter = make_interpreter();
+        // F is still held from before, 7 is pressed while waiting
+        interpreter.input_driver.events = vec![InputEvent::KeyDown(0xF)];
+        interpreter.poll_input().unwrap();
+        interpreter.input_driver.events = vec![InputEvent::KeyDown(0x7)];
+        assert_eq!(interpreter.execute_opcode(0xF40A), ProgramCounter::Next);
+        assert_eq!(interpreter.registers.v[4], 0x7);
+    }
+    #[test]
+    fn test_quit_while_waiting_for_key() {
+        let mut interpreter = make_interpreter();
+        interpreter.input_driver.events = vec![InputEvent::Quit];
+        interpreter.execute_opcode(0xF40A);
+        assert_eq!(interpreter.registers.v[4], 0);
+        assert!(interpreter.poll_input().is_err());
+    }
+    mod mocks {
         use crate::{
             displays::{Drawable, GamePixels},
-            input_driver::EventPollable,
-            keypads::Keypad,
+            input_driver::{EventPollable, InputEvent, TimedEvent},
             timers::TimerActions,
         };
 
         pub(crate) struct TestDisplay {
             pub(crate) pixels: GamePixels,
+            pub(crate) frames_ended: u32,
         }
         impl TestDisplay {
             pub(crate) fn new() -> TestDisplay {
                 TestDisplay {
                     pixels: GamePixels::new(),
+                    frames_ended: 0,
                 }
             }
         }
@@ -286,10 +605,23 @@ mod tests {
             fn clear(&mut self) {
                 self.pixels = GamePixels::new()
             }
+
+            fn pixels(&self) -> &GamePixels {
+                &self.pixels
+            }
+
+            fn restore(&mut self, pixels: GamePixels) {
+                self.pixels = pixels
+            }
+
+            fn end_frame(&mut self) {
+                self.frames_ended += 1;
+            }
         }
 
         pub(crate) struct TestInput {
-            events: Vec<Keycode>,
+            // returned by the next poll
+            pub(crate) events: Vec<InputEvent>,
         }
         impl TestInput {
             pub(crate) fn new() -> TestInput {
@@ -297,11 +629,11 @@ mod tests {
             }
         }
         impl EventPollable for TestInput {
-            fn poll(&mut self) -> Result<crate::keypads::Keypad, ()> {
-                Ok(Keypad {
-                    keypad: [false; 16],
-                    last_pressed: None,
-                })
+            fn poll(&mut self, now: u64) -> Vec<TimedEvent> {
+                self.events
+                    .drain(..)
+                    .map(|event| TimedEvent { at: now, event })
+                    .collect()
             }
         }
 
@@ -310,7 +642,7 @@ mod tests {
         }
         impl TestTimer {
             pub(crate) fn new(value: u8) -> TestTimer {
-                TestTimer { value: value }
+                TestTimer { value }
             }
         }
         impl TimerActions for TestTimer {
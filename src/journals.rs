@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+
+use crate::debuggers::WatchHit;
+use crate::displays::GamePixels;
+
+/**
+ * How to undo what an instruction did to the framebuffer.
+ */
+pub enum PixelsUndo {
+    // XOR drawing is its own inverse, so drawing the sprite again restores the pixels
+    Sprite(Vec<u8>, (usize, usize)),
+    Frame(Box<GamePixels>),
+}
+
+/**
+ * Everything one instruction changed, as the values from before it was executed.
+ */
+pub struct JournalEntry {
+    pub v: [u8; 16],
+    pub i: u16,
+    pub pc: u16,
+    pub sp: usize,
+    pub dt: u8,
+    pub st: u8,
+    pub frame_progress: u32,
+    // (stack index, old return address)
+    pub stack: Option<(usize, u16)>,
+    // (address, old value), in the order they were written
+    pub memory: Vec<(u16, u8)>,
+    pub pixels: Option<PixelsUndo>,
+    // watchpoints the instruction triggered, for reverse-continue
+    pub hits: Vec<WatchHit>,
+}
+
+/**
+ * An undo journal of the most recent `capacity` instructions.
+ */
+pub struct Journal {
+    entries: VecDeque<JournalEntry>,
+    capacity: usize,
+}
+
+impl Journal {
+    pub fn new(capacity: usize) -> Journal {
+        Journal {
+            entries: VecDeque::with_capacity(capacity.min(1 << 16)),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, entry: JournalEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn pop(&mut self) -> Option<JournalEntry> {
+        self.entries.pop_back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use sdl2::keyboard::Scancode;
+
+use Scancode::*;
+
+// the CHIP-8 keys bound by each preset, row by row as on the COSMAC VIP keypad
+pub(crate) const KEYPAD_ORDER: [u8; 16] = [
+    0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF,
+];
+
+const PRESETS: [(&str, [Scancode; 16]); 3] = [
+    // the keypad spread over both hands
+    (
+        "default",
+        [Q, W, E, R, A, S, D, F, U, I, O, P, J, K, L, Semicolon],
+    ),
+    // the conventional layout, the keypad in the top left block of the keyboard
+    (
+        "cosmac",
+        [Num1, Num2, Num3, Num4, Q, W, E, R, A, S, D, F, Z, X, C, V],
+    ),
+    // the left two columns of the keypad for the left player and the right two for the
+    // right player, like two player games split it
+    (
+        "two-player",
+        [
+            Num1, Num2, Num9, Num0, Q, W, O, P, A, S, L, Semicolon, Z, X, Period, Slash,
+        ],
+    ),
+];
+
+/**
+ * Which physical keys press which CHIP-8 key. Keys are bound by scancode, so the layout
+ * stays in place on AZERTY or Dvorak keyboards.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Keymap {
+    keys: [Vec<Scancode>; 16],
+}
+
+impl Keymap {
+    pub fn preset(name: &str) -> Result<Keymap, String> {
+        let (_, scancodes) = PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .ok_or(format!(
+                "unknown keymap preset {:?}, expected one of {}",
+                name,
+                PRESETS.map(|(name, _)| name).join(", ")
+            ))?;
+        let mut keys: [Vec<Scancode>; 16] = Default::default();
+        for (key, scancode) in KEYPAD_ORDER.iter().zip(scancodes) {
+            keys[*key as usize].push(*scancode);
+        }
+        Ok(Keymap { keys })
+    }
+
+    /**
+     * Parses a keymap file on top of `base`. Each line binds a CHIP-8 key to one or more
+     * SDL scancode names, replacing its bindings in `base`, e.g. `5 = W, Up`; an empty list
+     * unbinds the key. A `preset = name` line starts over from a preset. `#` starts a
+     * comment.
+     */
+    pub fn parse(text: &str, base: Keymap) -> Result<Keymap, String> {
+        let mut keymap = base;
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, scancodes) = line
+                .split_once('=')
+                .ok_or(format!("line {}: expected `key = scancodes`", idx + 1))?;
+            let (key, scancodes) = (key.trim(), scancodes.trim());
+            if key == "preset" {
+                keymap = Keymap::preset(scancodes)?;
+                continue;
+            }
+            let key = match u8::from_str_radix(key, 16) {
+                Ok(key) if key < 16 => key,
+                _ => return Err(format!("line {}: invalid CHIP-8 key {:?}", idx + 1, key)),
+            };
+            keymap.keys[key as usize] = scancodes
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    Scancode::from_name(name).ok_or(format!(
+                        "line {}: unknown scancode {:?}",
+                        idx + 1,
+                        name
+                    ))
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(keymap)
+    }
+
+    /**
+     * Loads a preset by name or else a keymap file on top of the default preset.
+     */
+    pub fn load(spec: &str) -> Result<Keymap, String> {
+        if let Ok(keymap) = Keymap::preset(spec) {
+            return Ok(keymap);
+        }
+        let text = fs::read_to_string(spec).map_err(|e| format!("{}: {}", spec, e))?;
+        Keymap::parse(&text, Keymap::default()).map_err(|e| format!("{}: {}", spec, e))
+    }
+
+    /**
+     * Binds the arrow keys, Space and Return to the CHIP-8 keys a game uses for `up`,
+     * `down`, `left`, `right`, `a` and `b`, as the ROM database lists them, besides their
+     * usual keys. Keys the keymap already uses for something else are left alone.
+     */
+    pub fn with_key_hints(self, hints: &BTreeMap<String, u8>) -> Keymap {
+        let mut keymap = self;
+        for (name, key) in hints {
+            let scancode = match name.as_str() {
+                "up" => Up,
+                "down" => Down,
+                "left" => Left,
+                "right" => Right,
+                "a" => Space,
+                "b" => Return,
+                _ => continue,
+            };
+            if *key >= 16 || keymap.chip8_keys(scancode).next().is_some() {
+                continue;
+            }
+            keymap.keys[*key as usize].push(scancode);
+        }
+        keymap
+    }
+
+    /**
+     * Applies the overrides of the keymap file next to the ROM, e.g. `pong.keymap` for
+     * `pong.ch8`, if there is one.
+     */
+    pub fn with_rom_overrides(self, rom_path: &str) -> Result<Keymap, String> {
+        let path = Path::new(rom_path).with_extension("keymap");
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                Keymap::parse(&text, self).map_err(|e| format!("{}: {}", path.display(), e))
+            }
+            Err(_) => Ok(self),
+        }
+    }
+
+    /**
+     * The CHIP-8 keys `scancode` presses.
+     */
+    pub fn chip8_keys(&self, scancode: Scancode) -> impl Iterator<Item = u8> + '_ {
+        (0..16u8).filter(move |key| self.keys[*key as usize].contains(&scancode))
+    }
+
+    pub fn scancodes(&self, key: u8) -> &[Scancode] {
+        &self.keys[key as usize]
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset(PRESETS[0].0).unwrap()
+    }
+}
+
+#[cfg(test)]
+#[path = "./keymaps_test.rs"]
+mod keymaps_test;
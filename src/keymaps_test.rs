@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use sdl2::keyboard::Scancode;
+
+    use crate::keymaps::Keymap;
+
+    #[test]
+    fn test_default_preset_matches_original_layout() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.scancodes(0x1), [Scancode::Q]);
+        assert_eq!(keymap.scancodes(0x0), [Scancode::K]);
+        assert_eq!(keymap.scancodes(0xF), [Scancode::Semicolon]);
+    }
+
+    #[test]
+    fn test_cosmac_preset() {
+        let keymap = Keymap::preset("cosmac").unwrap();
+        assert_eq!(keymap.chip8_keys(Scancode::Num4).collect::<Vec<_>>(), [0xC]);
+        assert_eq!(keymap.chip8_keys(Scancode::X).collect::<Vec<_>>(), [0x0]);
+        assert_eq!(keymap.chip8_keys(Scancode::K).count(), 0);
+    }
+
+    #[test]
+    fn test_parse_overrides_mentioned_keys() {
+        let keymap = Keymap::parse(
+            "# pong\n1 = Up, W\n4 = Down # player 1\n",
+            Keymap::default(),
+        )
+        .unwrap();
+        assert_eq!(keymap.scancodes(0x1), [Scancode::Up, Scancode::W]);
+        assert_eq!(keymap.scancodes(0x4), [Scancode::Down]);
+        assert_eq!(keymap.scancodes(0x2), [Scancode::W]);
+        assert_eq!(
+            keymap.chip8_keys(Scancode::W).collect::<Vec<_>>(),
+            [0x1, 0x2]
+        );
+    }
+
+    #[test]
+    fn test_parse_preset_and_unbind() {
+        let keymap = Keymap::parse("preset = two-player\nF =", Keymap::default()).unwrap();
+        assert_eq!(keymap.scancodes(0x3), [Scancode::Num9]);
+        assert!(keymap.scancodes(0xF).is_empty());
+    }
+
+    #[test]
+    fn test_key_hints() {
+        let hints = [
+            ("left".to_string(), 0x7),
+            ("a".to_string(), 0x4),
+            ("b".to_string(), 0x10),
+        ]
+        .into();
+        let keymap = Keymap::default().with_key_hints(&hints);
+        assert_eq!(keymap.scancodes(0x7), [Scancode::U, Scancode::Left]);
+        assert_eq!(keymap.scancodes(0x4), [Scancode::A, Scancode::Space]);
+        assert_eq!(keymap.chip8_keys(Scancode::Return).count(), 0);
+
+        // keys the keymap binds itself stay as they are
+        let keymap = Keymap::parse("5 = Left", Keymap::default()).unwrap();
+        let keymap = keymap.with_key_hints(&hints);
+        assert_eq!(keymap.chip8_keys(Scancode::Left).collect::<Vec<_>>(), [0x5]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Keymap::parse("10 = A", Keymap::default()).is_err());
+        assert!(Keymap::parse("1 = NotAKey", Keymap::default()).is_err());
+        assert!(Keymap::parse("1 A", Keymap::default()).is_err());
+        assert!(Keymap::parse("preset = azerty", Keymap::default()).is_err());
+    }
+}
@@ -1,15 +1,58 @@
-extern crate sdl2;
-
+/**
+ * The state of the 16 CHIP-8 keys, built up from key down and up events. A key released
+ * sooner than `min_hold` after it was pressed stays pressed until then, so games that
+ * only check the keypad every so often don't miss quick taps.
+ */
 pub struct Keypad {
-    pub keypad: [bool; 16],
-    pub last_pressed: Option<u8>,
+    held: [bool; 16],
+    // when each key was last pressed, in emulated time
+    pressed_at: [u64; 16],
+    // released keys still showing as pressed until `min_hold` passed
+    latched: [bool; 16],
+    min_hold: u64,
 }
 
 impl Keypad {
-    pub fn new() -> Keypad {
+    pub fn new(min_hold: u64) -> Keypad {
         Keypad {
-            keypad: [false; 16],
-            last_pressed: None,
+            held: [false; 16],
+            pressed_at: [0; 16],
+            latched: [false; 16],
+            min_hold,
         }
     }
+
+    pub fn press(&mut self, key: u8, at: u64) {
+        self.held[key as usize] = true;
+        self.pressed_at[key as usize] = at;
+    }
+
+    pub fn release(&mut self, key: u8, at: u64) {
+        let key = key as usize;
+        self.held[key] = false;
+        self.latched[key] = at < self.pressed_at[key] + self.min_hold;
+    }
+
+    /**
+     * Lets go of latched keys that were shown as pressed for long enough by `now`.
+     */
+    pub fn update(&mut self, now: u64) {
+        for (latched, pressed_at) in self.latched.iter_mut().zip(self.pressed_at) {
+            if now >= pressed_at + self.min_hold {
+                *latched = false;
+            }
+        }
+    }
+
+    pub fn is_pressed(&self, key: u8) -> bool {
+        self.held[key as usize] || self.latched[key as usize]
+    }
+
+    pub fn state(&self) -> [bool; 16] {
+        std::array::from_fn(|key| self.is_pressed(key as u8))
+    }
 }
+
+#[cfg(test)]
+#[path = "./keypads_test.rs"]
+mod keypads_test;
@@ -0,0 +1,27 @@
+#[cfg(test)]
+mod tests {
+    use crate::keypads::Keypad;
+
+    #[test]
+    fn test_held_key() {
+        let mut keypad = Keypad::new(16);
+        keypad.press(0x5, 100);
+        keypad.update(200);
+        assert!(keypad.is_pressed(0x5));
+        keypad.release(0x5, 200);
+        assert!(!keypad.is_pressed(0x5));
+    }
+
+    #[test]
+    fn test_quick_tap_is_latched() {
+        let mut keypad = Keypad::new(16);
+        keypad.press(0xA, 100);
+        keypad.release(0xA, 100);
+        assert!(keypad.is_pressed(0xA));
+        keypad.update(115);
+        assert!(keypad.is_pressed(0xA));
+        keypad.update(116);
+        assert!(!keypad.is_pressed(0xA));
+        assert_eq!(keypad.state(), [false; 16]);
+    }
+}
@@ -0,0 +1,40 @@
+/*!
+ * A CHIP-8 emulator. The binary plays ROMs with SDL or in a terminal; the modules can
+ * also be used on their own, e.g. `roms::Rom::from_bytes` to load a ROM from memory.
+ */
+
+extern crate sdl2;
+
+pub mod assemblers;
+pub mod beepers;
+pub mod commands;
+pub mod configs;
+pub mod consoles;
+pub mod controllers;
+pub mod databases;
+pub mod debug_windows;
+pub mod debuggers;
+pub mod displays;
+pub mod effects;
+pub mod expressions;
+pub mod filters;
+pub mod fonts;
+pub mod glyphs;
+pub mod headless;
+pub mod hexadecimal_sprites;
+pub mod hotkeys;
+pub mod input_driver;
+pub mod interpreters;
+pub mod journals;
+pub mod keymaps;
+pub mod keypads;
+pub mod memory;
+pub mod onscreen_keypads;
+pub mod palettes;
+pub mod profilers;
+pub mod quirks;
+pub mod roms;
+pub mod terminals;
+pub mod timers;
+pub mod viewports;
+pub mod waves;
@@ -1,44 +1,580 @@
-extern crate sdl2;
-
-pub mod displays;
-pub mod hexadecimal_sprites;
-pub mod input_driver;
-pub mod interpreters;
-pub mod keypads;
-pub mod memory;
-pub mod timers;
-pub mod waves;
-
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use chip_8::{
+    assemblers, beepers, commands, configs, consoles, controllers, databases, debug_windows,
+    debuggers, displays, effects, filters, fonts, headless, hotkeys, input_driver, interpreters,
+    keymaps, memory, onscreen_keypads, palettes, quirks, roms, terminals, timers, viewports, waves,
+};
 
-use input_driver::InputDriver;
-use timers::{DelayTimer, SoundTimer};
-use waves::Audio;
+use beepers::{Beeper, NullBeeper, WavBeeper};
+use commands::{AsmArgs, BenchArgs, Cli, Command, DisasmArgs, InfoArgs, RunArgs};
+use configs::{Config, Settings, SettingsStore};
+use controllers::ControllerMap;
+use databases::{Database, KnownRom};
+use debug_windows::DebugWindow;
+use debuggers::Watchpoint;
+use displays::Drawable;
+use effects::Effects;
+use filters::Filter;
+use fonts::Font;
+use headless::{HeadlessDisplay, HeadlessInput};
+use hotkeys::Hotkeys;
+use input_driver::{EventPollable, InputDriver};
+use keymaps::Keymap;
+use onscreen_keypads::OnScreenKeypad;
+use palettes::Palette;
+use quirks::Quirks;
+use roms::Rom;
+use terminals::{TerminalBell, TerminalDisplay, TerminalGraphics, TerminalInput};
+use timers::{DelayTimer, SoundTimer, TimerActions};
+use viewports::Viewport;
+use waves::{Audio, Tone, Waveform, DEFAULT_FREQUENCY, DEFAULT_VOLUME};
 
-use crate::memory::Memory;
+use crate::interpreters::{DEFAULT_FAST_FORWARD, DEFAULT_INSTRUCTIONS_PER_FRAME, DEFAULT_TURBO};
+use crate::memory::{Layout, Memory, DEFAULT_FONT_ADDRESS, STARTING_MEMORY};
 use crate::{displays::Display, interpreters::Interpreter};
 
-fn main() -> Result<(), String> {
-    let args: Vec<String> = env::args().collect();
+// how long a key counts as held after the terminal last reported it, longer than the
+// usual delay before a terminal starts repeating a held key
+const TERMINAL_KEY_RELEASE: Duration = Duration::from_millis(550);
+
+fn main() -> ExitCode {
+    let result = match Cli::parse_args(env::args_os()).command {
+        Command::Run(args) => play(args, Mode::Play),
+        Command::Debug(args) => {
+            let mode = if args.window {
+                Mode::DebugWindow(args.reverse_window)
+            } else {
+                Mode::Debug(args.reverse_window)
+            };
+            play(args.run, mode)
+        }
+        Command::Disasm(args) => disasm(args),
+        Command::Asm(args) => asm(args),
+        Command::Info(args) => info(args),
+        Command::Bench(args) => bench(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/**
+ * What's known about a ROM and the settings to play it with.
+ */
+struct Setup {
+    hash: String,
+    known_rom: Option<KnownRom>,
+    config_path: Option<PathBuf>,
+    settings: Settings,
+}
+
+/**
+ * The database given with `--database`, or else the built-in one.
+ */
+fn load_database(path: Option<&str>) -> Result<Database, String> {
+    match path {
+        Some(path) => Database::load(path),
+        None => Ok(Database::embedded()),
+    }
+}
+
+/**
+ * Looks the ROM up in the database and the config file, and works out its settings with
+ * `overrides` from the command line on top.
+ */
+fn set_up(
+    rom: &Rom,
+    config_path: Option<PathBuf>,
+    database: &Database,
+    overrides: &Settings,
+) -> Result<Setup, String> {
+    let hash = configs::rom_hash(&rom.bytes);
+    let config_path = config_path.or_else(Config::default_path);
+    let known_rom = database.find(&hash);
+    let recommended = known_rom
+        .as_ref()
+        .map_or_else(Settings::default, |rom| rom.settings());
+
+    let config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::parse("", Path::new(""))?,
+    };
+    let settings = config.settings(&hash, &rom.name, &recommended, overrides)?;
+    Ok(Setup {
+        hash,
+        known_rom,
+        config_path,
+        settings,
+    })
+}
+
+fn play(args: RunArgs, mode: Mode) -> Result<(), String> {
+    let rom = read_rom(&args.rom, args.entry.as_deref())?;
+    let mut overrides = args.settings();
+    let mut platform = None;
+    let database = load_database(args.database.as_deref())?;
+    if let Some(id) = &args.platform {
+        let chosen = database.platform(id)?;
+        if !chosen.is_supported() {
+            eprintln!(
+                "{} isn't supported, playing as CHIP-8 with its quirks",
+                chosen.name
+            );
+        }
+        overrides = chosen.settings().overridden_by(&overrides);
+        platform = Some(chosen.clone());
+    }
+    let Setup {
+        hash,
+        known_rom,
+        config_path,
+        settings,
+    } = set_up(&rom, args.config.clone(), &database, &overrides)?;
+    if let Some(rom) = known_rom.as_ref().filter(|rom| !rom.is_supported()) {
+        if let Some(platform) = &rom.platform {
+            eprintln!(
+                "{} was made for {}, which this emulator doesn't support",
+                rom.title, platform.name
+            );
+        }
+    }
+
+    let speed = check_speed(
+        "instructions per frame",
+        settings.speed.unwrap_or(DEFAULT_INSTRUCTIONS_PER_FRAME),
+    )?;
+    let fast_forward = check_speed(
+        "fast-forward speed",
+        settings.fast_forward.unwrap_or(DEFAULT_FAST_FORWARD),
+    )?;
+    let turbo = check_speed("turbo speed", settings.turbo.unwrap_or(DEFAULT_TURBO))?;
+
+    let tone = Tone {
+        frequency: settings.frequency.unwrap_or(DEFAULT_FREQUENCY),
+        volume: settings.volume.unwrap_or(DEFAULT_VOLUME),
+        waveform: match &settings.waveform {
+            Some(waveform) => Waveform::parse(waveform)?,
+            None => Waveform::Square,
+        },
+    }
+    .validate()?;
+    let muted = settings.mute.unwrap_or(false);
+
+    let start_address = settings.start_address.unwrap_or(STARTING_MEMORY);
+    let platform = platform.or(known_rom.as_ref().and_then(|rom| rom.platform.clone()));
+    rom.check_size(start_address, platform.as_ref())?;
+    let mut layout = Layout {
+        start: start_address,
+        font: match &settings.font {
+            Some(spec) => Font::load(spec)?,
+            None => Font::new(),
+        },
+        font_address: settings.font_address.unwrap_or(DEFAULT_FONT_ADDRESS),
+        blobs: vec![],
+    };
+    for (path, address) in &args.load {
+        let blob = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        layout.blobs.push((*address, blob));
+    }
+    let program = Memory::with_layout(&rom.bytes, &layout)?;
+    let options = Options {
+        profile_path: args.profile.clone(),
+        watchpoints: args.watch.clone(),
+        mode,
+        speed,
+        speeds: (fast_forward, turbo),
+        quirks: settings.quirks.unwrap_or_default(),
+        start_address,
+        seed: args.seed,
+        settings_store: config_path.map(|path| SettingsStore {
+            path,
+            hash,
+            settings: settings.clone(),
+        }),
+    };
+    let debugging = !matches!(options.mode, Mode::Play);
+
+    if args.headless {
+        if debugging {
+            return Err("the debuggers need a window or the terminal".to_string());
+        }
+        let options = Options {
+            mode: Mode::Headless(args.frames),
+            ..options
+        };
+        let delay_timer = DelayTimer::new();
+        let beeper = open_beeper(args.wav, tone, || Ok(NullBeeper));
+        let sound_timer = SoundTimer::new(beeper, muted);
+        let interpreter = Interpreter::new(
+            program,
+            HeadlessDisplay::new(),
+            HeadlessInput,
+            sound_timer,
+            delay_timer,
+        );
+        return run(interpreter, options, None);
+    }
+
+    let palette = match &settings.palette {
+        Some(spec) => Palette::parse(spec)?,
+        None => Palette::default(),
+    };
+    let mut keymap = match &settings.keymap {
+        Some(spec) => Keymap::load(spec)?,
+        None => Keymap::default(),
+    };
+    if let Some(rom) = &known_rom {
+        keymap = keymap.with_key_hints(&rom.keys);
+    }
+    let keymap = keymap.with_rom_overrides(&args.rom)?;
 
-    let file_path = args.get(1).ok_or("Usage: chip8 /path/to/file")?;
+    if let Some(graphics) = &args.terminal {
+        if debugging {
+            return Err("the debuggers can't share the terminal with the game".to_string());
+        }
+        let mut display = TerminalDisplay::new(TerminalGraphics::parse(graphics)?, palette)?;
+        if let Some(rom) = &known_rom {
+            display.set_title(&rom.describe());
+        }
+        let input = TerminalInput::new(TERMINAL_KEY_RELEASE, keymap)?;
+        let delay_timer = DelayTimer::new();
+        let beeper = open_beeper(args.wav, tone, || Ok(TerminalBell::new()));
+        let sound_timer = SoundTimer::new(beeper, muted);
+        let interpreter = Interpreter::new(program, display, input, sound_timer, delay_timer);
+        return run(interpreter, options, None);
+    }
+
+    let mut viewport = Viewport::new();
+    if let Some(scaling) = &settings.scaling {
+        viewport.scaling = Viewport::parse_scaling(scaling)?;
+    }
+    if let Some(scale) = settings.scale {
+        viewport.window_scale = scale.max(1);
+    }
+    if let Some(rotation) = settings.rotate {
+        viewport.rotation = Viewport::parse_rotation(&rotation.to_string())?;
+    }
+    if let Some(flip) = &settings.flip {
+        (viewport.flip_horizontal, viewport.flip_vertical) = Viewport::parse_flip(flip)?;
+    }
+    viewport.grid = settings.grid.unwrap_or(false);
+    viewport.fullscreen = settings.fullscreen.unwrap_or(false);
+    let filter = match &settings.filter {
+        Some(name) => Filter::parse(name)?,
+        None => Filter::None,
+    };
+    let keypad = match settings.keypad.as_deref() {
+        None | Some("none") => None,
+        Some(placement) => Some(OnScreenKeypad::new(OnScreenKeypad::parse_placement(
+            placement,
+        )?)),
+    };
+    let mut effects = Effects::new();
+    match settings.phosphor {
+        Some(half_life) if half_life > 0.0 => effects.set_phosphor(half_life),
+        Some(half_life) if half_life < 0.0 => {
+            return Err(format!("invalid phosphor half-life {}", half_life))
+        }
+        _ => (),
+    }
+    match settings.blend.as_deref() {
+        None | Some("none") => (),
+        Some(blend) => {
+            let (mode, frames) = Effects::parse_blend(blend)?;
+            effects.set_blend(mode, frames);
+        }
+    }
 
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
-    let audio_subsystem = sdl_context.audio()?;
 
-    let program: Memory = Memory::new(file_path)?;
+    let debug_window = if matches!(options.mode, Mode::DebugWindow(_)) {
+        Some(DebugWindow::new(&video_subsystem)?)
+    } else {
+        None
+    };
 
     // create Interpreter
-    let display = Display::new(video_subsystem)?;
-    let audio = Audio::new(audio_subsystem)?;
-    let input = InputDriver::new(&sdl_context);
-    let delay_timer = DelayTimer { value: 0 };
-    let sound_timer = SoundTimer::new(audio);
+    let canvas = displays::open_window(
+        video_subsystem,
+        &viewport,
+        keypad.as_ref(),
+        settings.vsync.unwrap_or(false),
+    )?;
+    let textures = canvas.texture_creator();
+    let mut display = Display::new(
+        canvas, &textures, palette, effects, viewport, filter, keypad,
+    )?;
+    if let Some(rom) = &known_rom {
+        display.set_title(&rom.describe());
+    }
+    let mut controller_map = ControllerMap::new();
+    if let Some(rom) = &known_rom {
+        controller_map = controller_map.with_key_hints(&rom.keys);
+    }
+    let controller_map = match &settings.controller {
+        Some(path) => ControllerMap::load(path, controller_map)?,
+        None => controller_map,
+    }
+    .with_rom_overrides(&args.rom)?;
+    let hotkeys = match &settings.hotkeys {
+        Some(path) => Hotkeys::load(path)?,
+        None => Hotkeys::new(),
+    };
+    let mut input = InputDriver::new(&sdl_context, keymap, controller_map, hotkeys);
+    if let Some(window) = &debug_window {
+        input.forward_window(window.id());
+    }
+    let delay_timer = DelayTimer::new();
+
+    let beeper = open_beeper(args.wav, tone, || Audio::new(sdl_context.audio()?, tone));
+    let sound_timer = SoundTimer::new(beeper, muted);
+    let interpreter = Interpreter::new(program, display, input, sound_timer, delay_timer);
+    run(interpreter, options, debug_window)
+}
+
+/**
+ * Where the sound goes: the WAV file if one was given, otherwise `speaker`, or nowhere if
+ * there's no audio device.
+ */
+fn open_beeper<B: Beeper + 'static>(
+    wav: Option<PathBuf>,
+    tone: Tone,
+    speaker: impl FnOnce() -> Result<B, String>,
+) -> Box<dyn Beeper> {
+    if let Some(path) = wav {
+        return Box::new(WavBeeper::new(path, tone));
+    }
+    match speaker() {
+        Ok(beeper) => Box::new(beeper),
+        Err(e) => {
+            eprintln!("playing without sound: {}", e);
+            Box::new(NullBeeper)
+        }
+    }
+}
+
+/**
+ * Reads the ROM, saying which option the entry came from if it was the problem.
+ */
+fn read_rom(path: &str, entry: Option<&str>) -> Result<Rom, String> {
+    Rom::read(path, entry).map_err(|e| match entry {
+        Some(entry) => format!("{} (--entry {})", e, entry),
+        None => e,
+    })
+}
+
+fn check_speed(what: &str, speed: u32) -> Result<u32, String> {
+    if speed == 0 {
+        return Err(format!("the {} must be at least 1", what));
+    }
+    Ok(speed)
+}
+
+fn disasm(args: DisasmArgs) -> Result<(), String> {
+    let rom = read_rom(&args.rom, args.entry.as_deref())?;
+    print!(
+        "{}",
+        assemblers::disassemble_program(&rom.bytes, args.start_address)
+    );
+    Ok(())
+}
+
+fn asm(args: AsmArgs) -> Result<(), String> {
+    let source = fs::read_to_string(&args.source)
+        .map_err(|e| format!("{}: {}", args.source.display(), e))?;
+    let program = assemblers::assemble(&source, args.start_address)
+        .map_err(|e| format!("{}: {}", args.source.display(), e))?;
+    let output = args
+        .output
+        .unwrap_or_else(|| args.source.with_extension("ch8"));
+    fs::write(&output, &program).map_err(|e| format!("{}: {}", output.display(), e))?;
+    eprintln!("wrote {} bytes to {}", program.len(), output.display());
+    Ok(())
+}
+
+fn info(args: InfoArgs) -> Result<(), String> {
+    let rom = read_rom(&args.rom, args.entry.as_deref())?;
+    let database = load_database(args.database.as_deref())?;
+    let setup = set_up(&rom, args.config, &database, &Settings::default())?;
+    println!("file:     {}", rom.name);
+    println!("size:     {} bytes", rom.bytes.len());
+    println!("sha1:     {}", setup.hash);
+    match &setup.known_rom {
+        Some(known) => {
+            println!("title:    {}", known.describe());
+            if let Some(platform) = &known.platform {
+                let support = if platform.is_supported() {
+                    ""
+                } else {
+                    " (not supported)"
+                };
+                println!("platform: {}{}", platform.name, support);
+            }
+        }
+        None => println!("title:    not in the ROM database"),
+    }
+    if let Some(path) = &setup.config_path {
+        println!("config:   {}", path.display());
+    }
+    let settings = toml::to_string(&setup.settings).map_err(|e| e.to_string())?;
+    if !settings.is_empty() {
+        println!("\n{}", settings.trim_end());
+    }
+    Ok(())
+}
+
+fn bench(args: BenchArgs) -> Result<(), String> {
+    let rom = read_rom(&args.rom, args.entry.as_deref())?;
+    let program = Memory::from_rom(&rom.bytes, args.start_address)?;
+    let delay_timer = DelayTimer::new();
+    let sound_timer = SoundTimer::new(NullBeeper, true);
+    let mut interpreter = Interpreter::new(
+        program,
+        HeadlessDisplay::new(),
+        HeadlessInput,
+        sound_timer,
+        delay_timer,
+    );
+    interpreter.set_start_address(args.start_address);
+    interpreter.set_instructions_per_frame(args.speed.unwrap_or(DEFAULT_INSTRUCTIONS_PER_FRAME));
+    interpreter.set_quirks(args.quirks.unwrap_or_default());
+    interpreter.set_seed(args.seed);
+    interpreter.set_throttled(false);
+
+    let started = Instant::now();
+    let mut frames = 0;
+    while frames < args.frames && interpreter.run_frame().is_ok() {
+        frames += 1;
+    }
+    let seconds = started.elapsed().as_secs_f64();
+
+    if frames < args.frames {
+        eprintln!("the ROM waited for a key after {} frames", frames);
+    }
+    let instructions = interpreter.instructions();
+    println!(
+        "{} frames, {} instructions in {:.3} s: {:.0} instructions per second, {:.1} times real time",
+        frames,
+        instructions,
+        seconds,
+        instructions as f64 / seconds,
+        frames as f64 / 60.0 / seconds
+    );
+    Ok(())
+}
+
+/**
+ * How to run the game.
+ */
+enum Mode {
+    Play,
+    // without a window as fast as possible, optionally stopping after a number of frames
+    Headless(Option<u64>),
+    // in the terminal debugger, able to undo that many instructions
+    Debug(usize),
+    DebugWindow(usize),
+}
+
+struct Options {
+    profile_path: Option<String>,
+    watchpoints: Vec<Watchpoint>,
+    mode: Mode,
+    // instructions per frame
+    speed: u32,
+    // fast-forward and turbo speed multipliers
+    speeds: (u32, u32),
+    quirks: Quirks,
+    start_address: u16,
+    seed: Option<u64>,
+    settings_store: Option<SettingsStore>,
+}
+
+/**
+ * Runs the game, or one of the debuggers, with whichever frontend it was created with.
+ */
+fn run<D, I, ST, DT>(
+    mut interpreter: Interpreter<D, I, ST, DT>,
+    options: Options,
+    debug_window: Option<DebugWindow>,
+) -> Result<(), String>
+where
+    I: EventPollable,
+    D: Drawable,
+    ST: TimerActions,
+    DT: TimerActions,
+{
+    let Options {
+        profile_path,
+        watchpoints,
+        mode,
+        speed,
+        speeds: (fast_forward, turbo),
+        quirks,
+        start_address,
+        seed,
+        settings_store,
+    } = options;
+
+    interpreter.set_start_address(start_address);
+    interpreter.set_instructions_per_frame(speed);
+    interpreter.set_speeds(fast_forward, turbo);
+    interpreter.set_quirks(quirks);
+    if let Some(seed) = seed {
+        interpreter.set_seed(seed);
+    }
+    if let Some(store) = settings_store {
+        interpreter.set_settings_store(store);
+    }
+
+    if profile_path.is_some() {
+        interpreter.enable_profiler();
+    }
+    for watchpoint in watchpoints {
+        let debugger = interpreter.debugger_mut();
+        debugger.trace = true;
+        debugger.add_watchpoint(watchpoint);
+    }
 
-    let mut interpreter = Interpreter::new(program, display, input, sound_timer, delay_timer);
+    match mode {
+        Mode::Play => interpreter.execute_program(),
+        Mode::Headless(frames) => {
+            interpreter.set_throttled(false);
+            let mut frame = 0;
+            while !interpreter.is_halted() && frames.is_none_or(|frames| frame < frames) {
+                match interpreter.run_frame() {
+                    Ok(Some(_)) => interpreter.debugger_mut().resume(),
+                    Ok(None) => (),
+                    Err(_) => break,
+                }
+                frame += 1;
+            }
+            print!("{}", interpreter.display().pixels().to_text());
+        }
+        Mode::Debug(reverse_window) => {
+            interpreter.enable_journal(reverse_window);
+            consoles::debug(&mut interpreter);
+        }
+        Mode::DebugWindow(reverse_window) => {
+            interpreter.enable_journal(reverse_window);
+            if let Some(mut window) = debug_window {
+                debug_windows::run(&mut interpreter, &mut window)?;
+            }
+        }
+    }
 
-    interpreter.execute_program();
+    if let (Some(path), Some(report)) = (profile_path, interpreter.profile_report()) {
+        fs::write(path, report).map_err(|e| e.to_string())?;
+    }
 
     Ok(())
 }
@@ -1,34 +1,65 @@
-use crate::hexadecimal_sprites::{HEXADECIMAL_SPRITES, HEX_SIZE};
-use std::cmp::min;
-use std::fs::read;
+use crate::fonts::Font;
+use crate::hexadecimal_sprites::HEX_SIZE;
+use crate::roms::Rom;
 
 pub const STARTING_MEMORY: u16 = 0x200;
-const MEM_SIZE: usize = 4096;
-const MAX_PROGRAM_SIZE: usize = MEM_SIZE - (STARTING_MEMORY as usize);
+pub const MEM_SIZE: usize = 4096;
+// where the hex font goes unless told otherwise, many interpreters use 0x050 instead
+pub const DEFAULT_FONT_ADDRESS: u16 = 0x000;
 
+#[derive(Clone)]
 pub struct Memory {
     pub value: [u8; MEM_SIZE],
+    font_address: u16,
+}
+
+/**
+ * Where the ROM, the font and any other data are loaded.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Layout {
+    // where the ROM is loaded and started, 0x600 for ETI-660 programs
+    pub start: u16,
+    pub font: Font,
+    pub font_address: u16,
+    // data loaded alongside the ROM, by address
+    pub blobs: Vec<(u16, Vec<u8>)>,
+}
+
+impl Layout {
+    pub fn new() -> Layout {
+        Layout {
+            start: STARTING_MEMORY,
+            font: Font::new(),
+            font_address: DEFAULT_FONT_ADDRESS,
+            blobs: vec![],
+        }
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::new()
+    }
 }
 
 impl Memory {
-    pub fn query_hex_location(digit: u8) -> Option<u8> {
+    /**
+     * Where the sprite of the hex digit is, for FX29.
+     */
+    pub fn query_hex_location(&self, digit: u8) -> Option<u16> {
         if digit < 16 {
-            Some(digit * (HEX_SIZE as u8))
+            Some(self.font_address + digit as u16 * HEX_SIZE as u16)
         } else {
             None
         }
     }
 
-    fn load_hex(mem_arr: &mut [u8; MEM_SIZE]) {
-        let hex_iter = (0..(HEXADECIMAL_SPRITES.len() * HEX_SIZE))
-            .step_by(HEX_SIZE)
-            .zip(HEXADECIMAL_SPRITES);
-
-        for (mem_start, sprite) in hex_iter {
-            for (si, byte) in sprite.iter().enumerate() {
-                mem_arr[mem_start + si] = *byte;
-            }
-        }
+    fn load_hex(mem_arr: &mut [u8; MEM_SIZE], font: &Font, font_address: u16) {
+        let start = font_address as usize;
+        let large_start = start + font.small.len();
+        mem_arr[start..large_start].copy_from_slice(&font.small);
+        mem_arr[large_start..large_start + font.large.len()].copy_from_slice(&font.large);
     }
 
     /**
@@ -44,24 +75,83 @@ impl Memory {
 
     pub fn empty() -> Memory {
         let mut mem_arr = [0; MEM_SIZE];
-        Memory::load_hex(&mut mem_arr);
+        Memory::load_hex(&mut mem_arr, &Font::new(), DEFAULT_FONT_ADDRESS);
 
-        Memory { value: mem_arr }
+        Memory {
+            value: mem_arr,
+            font_address: DEFAULT_FONT_ADDRESS,
+        }
     }
     pub fn new(file_path: &str) -> Result<Memory, String> {
-        read(file_path)
-            .map(|bytes| {
-                let truncated_file = &bytes[0..min(MAX_PROGRAM_SIZE, bytes.len())];
-                let mut mem_arr: [u8; MEM_SIZE] = [0; MEM_SIZE];
-                // front load with hex sprites
-                Memory::load_hex(&mut mem_arr);
-                let mut i = STARTING_MEMORY as usize;
-                for b in truncated_file {
-                    mem_arr[i] = *b;
-                    i += 1;
-                }
-                Memory { value: mem_arr }
-            })
-            .map_err(|err| err.to_string())
+        let rom = Rom::read(file_path, None)?;
+        Memory::from_rom(&rom.bytes, STARTING_MEMORY).map_err(|e| format!("{}: {}", rom.name, e))
+    }
+
+    /**
+     * Memory with the hex sprites and `rom` loaded at `start`, usually 0x200. Fails if
+     * the ROM doesn't fit.
+     */
+    pub fn from_rom(rom: &[u8], start: u16) -> Result<Memory, String> {
+        let layout = Layout {
+            start,
+            ..Layout::new()
+        };
+        Memory::with_layout(rom, &layout)
+    }
+
+    /**
+     * Memory with `rom`, the hex sprites and the blobs loaded where `layout` says. Fails if
+     * anything doesn't fit or two of them overlap.
+     */
+    pub fn with_layout(rom: &[u8], layout: &Layout) -> Result<Memory, String> {
+        let mut regions = vec![
+            (
+                "the font".to_string(),
+                layout.font_address,
+                layout.font.size(),
+            ),
+            ("the ROM".to_string(), layout.start, rom.len()),
+        ];
+        for (address, blob) in &layout.blobs {
+            regions.push((
+                format!("the data at {:#05x}", address),
+                *address,
+                blob.len(),
+            ));
+        }
+
+        for (idx, (what, address, size)) in regions.iter().enumerate() {
+            let room = MEM_SIZE.saturating_sub(*address as usize);
+            if *size > room {
+                return Err(format!(
+                    "{} is {} bytes, but only {} bytes fit in memory from {:#05x}",
+                    what, size, room, address
+                ));
+            }
+            let end = *address as usize + size;
+            let overlapping = regions[..idx].iter().find(|(_, other, other_size)| {
+                (*address as usize) < *other as usize + other_size && (*other as usize) < end
+            });
+            if let Some((other, _, _)) = overlapping.filter(|_| *size > 0) {
+                return Err(format!("{} overlaps {}", what, other));
+            }
+        }
+
+        let mut mem_arr: [u8; MEM_SIZE] = [0; MEM_SIZE];
+        Memory::load_hex(&mut mem_arr, &layout.font, layout.font_address);
+        let start = layout.start as usize;
+        mem_arr[start..start + rom.len()].copy_from_slice(rom);
+        for (address, blob) in &layout.blobs {
+            let address = *address as usize;
+            mem_arr[address..address + blob.len()].copy_from_slice(blob);
+        }
+        Ok(Memory {
+            value: mem_arr,
+            font_address: layout.font_address,
+        })
     }
 }
+
+#[cfg(test)]
+#[path = "./memory_test.rs"]
+mod memory_test;
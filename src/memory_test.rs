@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use crate::fonts::Font;
+    use crate::hexadecimal_sprites::HEXADECIMAL_SPRITES;
+    use crate::memory::{Layout, Memory};
+
+    #[test]
+    fn test_from_rom() {
+        let memory = Memory::from_rom(&[0x12, 0x00], 0x200).unwrap();
+        assert_eq!(memory.read_instruction(0x200), 0x1200);
+        assert_eq!(memory.value[..5], HEXADECIMAL_SPRITES[0]);
+        assert_eq!(memory.query_hex_location(0xA), Some(50));
+        assert_eq!(memory.query_hex_location(0x10), None);
+    }
+
+    #[test]
+    fn test_rom_too_big() {
+        assert!(Memory::from_rom(&[0; 3584], 0x200).is_ok());
+        assert_eq!(
+            Memory::from_rom(&[0; 3585], 0x200).err(),
+            Some("the ROM is 3585 bytes, but only 3584 bytes fit in memory from 0x200".to_string())
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = Layout {
+            start: 0x600,
+            font: Font::builtin("schip").unwrap(),
+            font_address: 0x050,
+            blobs: vec![(0x800, vec![1, 2, 3])],
+        };
+        let memory = Memory::with_layout(&[0x16, 0x00], &layout).unwrap();
+        assert_eq!(memory.read_instruction(0x600), 0x1600);
+        assert_eq!(memory.value[0x50..0x55], HEXADECIMAL_SPRITES[0]);
+        assert_eq!(memory.value[..0x50], [0; 0x50]);
+        assert_eq!(memory.query_hex_location(1), Some(0x55));
+        // the big digits follow the small ones
+        assert_eq!(memory.value[0xA0..0xA2], [0x3C, 0x7E]);
+        assert_eq!(memory.value[0x800..0x803], [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_layout_overlaps() {
+        // SCHIP's big digits make the font reach past 0x200
+        let layout = Layout {
+            font: Font::builtin("schip").unwrap(),
+            font_address: 0x170,
+            ..Layout::new()
+        };
+        assert!(Memory::with_layout(&[0x12, 0x00], &layout).is_err());
+        let layout = Layout {
+            font_address: 0x1F0,
+            ..Layout::new()
+        };
+        assert_eq!(
+            Memory::with_layout(&[0x12, 0x00], &layout).err(),
+            Some("the ROM overlaps the font".to_string())
+        );
+        let layout = Layout {
+            blobs: vec![(0x201, vec![0])],
+            ..Layout::new()
+        };
+        assert_eq!(
+            Memory::with_layout(&[0x12, 0x00], &layout).err(),
+            Some("the data at 0x201 overlaps the ROM".to_string())
+        );
+        let layout = Layout {
+            blobs: vec![(0xFFF, vec![0, 0])],
+            ..Layout::new()
+        };
+        assert!(Memory::with_layout(&[], &layout).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use sdl2::rect::Rect;
+
+use crate::keymaps::KEYPAD_ORDER;
+
+// how many frames a key stays highlighted after the ROM tested it with SKP or SKNP
+const TESTED_FRAMES: u8 = 10;
+// the gap between keys as a fraction of their size
+const KEY_GAP: u32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KeypadPlacement {
+    // in a panel right of the game
+    Side,
+    // over the bottom right corner of the game
+    Overlay,
+}
+
+/**
+ * A 4x4 hex keypad drawn in the game's window that can be clicked or touched. It
+ * highlights the keys the ROM is testing, which shows which keys a game uses.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct OnScreenKeypad {
+    pub placement: KeypadPlacement,
+    pressed: [bool; 16],
+    // frames left to highlight each key
+    tested: [u8; 16],
+}
+
+impl OnScreenKeypad {
+    pub fn new(placement: KeypadPlacement) -> OnScreenKeypad {
+        OnScreenKeypad {
+            placement,
+            pressed: [false; 16],
+            tested: [0; 16],
+        }
+    }
+
+    pub fn parse_placement(spec: &str) -> Result<KeypadPlacement, String> {
+        match spec {
+            "side" => Ok(KeypadPlacement::Side),
+            "overlay" => Ok(KeypadPlacement::Overlay),
+            _ => Err(format!(
+                "unknown keypad placement {:?}, expected side or overlay",
+                spec
+            )),
+        }
+    }
+
+    pub fn placement_name(&self) -> &'static str {
+        match self.placement {
+            KeypadPlacement::Side => "side",
+            KeypadPlacement::Overlay => "overlay",
+        }
+    }
+
+    /**
+     * The width of the panel taken from the right of `window`, the game gets the rest.
+     */
+    pub fn panel_width(&self, window: (u32, u32)) -> u32 {
+        match self.placement {
+            KeypadPlacement::Side => u32::min(window.1 / 2, window.0 / 3),
+            KeypadPlacement::Overlay => 0,
+        }
+    }
+
+    /**
+     * The square the keys are laid out in.
+     */
+    pub fn area(&self, window: (u32, u32)) -> Rect {
+        match self.placement {
+            KeypadPlacement::Side => {
+                let side = self.panel_width(window);
+                let top = (window.1 as i32 - side as i32) / 2;
+                Rect::new((window.0 - side) as i32, top, side, side)
+            }
+            KeypadPlacement::Overlay => {
+                let side = u32::min(window.0, window.1) / 2;
+                Rect::new(
+                    (window.0 - side) as i32,
+                    (window.1 - side) as i32,
+                    side,
+                    side,
+                )
+            }
+        }
+    }
+
+    /**
+     * Each key with where it is drawn, row by row as on the COSMAC VIP keypad.
+     */
+    pub fn key_rects(&self, window: (u32, u32)) -> Vec<(u8, Rect)> {
+        let area = self.area(window);
+        let cell = area.width() / 4;
+        let gap = cell / KEY_GAP;
+        KEYPAD_ORDER
+            .iter()
+            .enumerate()
+            .map(|(idx, key)| {
+                let (column, row) = (idx as u32 % 4, idx as u32 / 4);
+                let rect = Rect::new(
+                    area.left() + (column * cell + gap / 2) as i32,
+                    area.top() + (row * cell + gap / 2) as i32,
+                    (cell - gap).max(1),
+                    (cell - gap).max(1),
+                );
+                (*key, rect)
+            })
+            .collect()
+    }
+
+    /**
+     * The key under `point` in window pixels.
+     */
+    pub fn key_at(&self, window: (u32, u32), point: (i32, i32)) -> Option<u8> {
+        self.key_rects(window)
+            .into_iter()
+            .find(|(_, rect)| rect.contains_point(point))
+            .map(|(key, _)| key)
+    }
+
+    pub fn show_pressed(&mut self, pressed: [bool; 16]) {
+        self.pressed = pressed;
+    }
+
+    pub fn is_pressed(&self, key: u8) -> bool {
+        self.pressed[key as usize]
+    }
+
+    pub fn test(&mut self, key: u8) {
+        self.tested[key as usize & 0xF] = TESTED_FRAMES;
+    }
+
+    pub fn is_tested(&self, key: u8) -> bool {
+        self.tested[key as usize] > 0
+    }
+
+    pub fn end_frame(&mut self) {
+        for frames in self.tested.iter_mut() {
+            *frames = frames.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./onscreen_keypads_test.rs"]
+mod onscreen_keypads_test;
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use crate::onscreen_keypads::{KeypadPlacement, OnScreenKeypad};
+
+    #[test]
+    fn test_side_panel() {
+        let keypad = OnScreenKeypad::new(KeypadPlacement::Side);
+        assert_eq!(keypad.panel_width((1600, 640)), 320);
+        let area = keypad.area((1600, 640));
+        assert_eq!((area.left(), area.top(), area.width()), (1280, 160, 320));
+    }
+
+    #[test]
+    fn test_overlay_takes_no_panel() {
+        let keypad = OnScreenKeypad::new(KeypadPlacement::Overlay);
+        assert_eq!(keypad.panel_width((1280, 640)), 0);
+        let area = keypad.area((1280, 640));
+        assert_eq!((area.left(), area.top(), area.width()), (960, 320, 320));
+    }
+
+    #[test]
+    fn test_key_at_follows_cosmac_layout() {
+        let keypad = OnScreenKeypad::new(KeypadPlacement::Side);
+        // the cells are 80 pixels wide starting at (1280, 160)
+        assert_eq!(keypad.key_at((1600, 640), (1320, 200)), Some(0x1));
+        assert_eq!(keypad.key_at((1600, 640), (1560, 200)), Some(0xC));
+        assert_eq!(keypad.key_at((1600, 640), (1400, 440)), Some(0x0));
+        assert_eq!(keypad.key_at((1600, 640), (1560, 440)), Some(0xF));
+        assert_eq!(keypad.key_at((1600, 640), (640, 320)), None);
+        // between two keys
+        assert_eq!(keypad.key_at((1600, 640), (1360, 200)), None);
+    }
+
+    #[test]
+    fn test_tested_keys_fade() {
+        let mut keypad = OnScreenKeypad::new(KeypadPlacement::Side);
+        keypad.test(0xA);
+        assert!(keypad.is_tested(0xA));
+        for _ in 0..10 {
+            keypad.end_frame();
+        }
+        assert!(!keypad.is_tested(0xA));
+    }
+}
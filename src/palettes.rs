@@ -0,0 +1,113 @@
+use sdl2::pixels::Color;
+
+/**
+ * The colours pixels are drawn with, indexed by pixel value: the background, the first
+ * and second bitplane and where both bitplanes are set.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Palette {
+    pub name: String,
+    pub colors: [Color; 4],
+}
+
+const fn rgb(hex: u32) -> Color {
+    Color::RGB((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+}
+
+// built-in themes in the order the palette hotkey cycles through them
+const THEMES: [(&str, [Color; 4]); 6] = [
+    (
+        "default",
+        [rgb(0x1e1e1e), rgb(0x00ff00), rgb(0x008800), rgb(0xaaffaa)],
+    ),
+    (
+        "classic",
+        [rgb(0x000000), rgb(0xffffff), rgb(0xaaaaaa), rgb(0x555555)],
+    ),
+    (
+        "lcd",
+        [rgb(0x9bbc0f), rgb(0x0f380f), rgb(0x306230), rgb(0x8bac0f)],
+    ),
+    (
+        "amber",
+        [rgb(0x1a0f00), rgb(0xffb000), rgb(0xb36b00), rgb(0xffe0a0)],
+    ),
+    (
+        "high-contrast",
+        [rgb(0x000000), rgb(0xffff00), rgb(0x00ffff), rgb(0xffffff)],
+    ),
+    // Okabe-Ito colours, which stay distinguishable with the common colour vision deficiencies
+    (
+        "colorblind",
+        [rgb(0x000000), rgb(0xe69f00), rgb(0x56b4e9), rgb(0xf0e442)],
+    ),
+];
+
+impl Palette {
+    /**
+     * Parses a theme name (`default`, `classic`, `lcd`, `amber`, `high-contrast`,
+     * `colorblind`) or 2 or 4 comma separated `#rrggbb` colours, background first.
+     * With 2 colours the second bitplane uses the foreground too.
+     */
+    pub fn parse(spec: &str) -> Result<Palette, String> {
+        if let Some((name, colors)) = THEMES.iter().find(|(name, _)| *name == spec) {
+            return Ok(Palette {
+                name: name.to_string(),
+                colors: *colors,
+            });
+        }
+        let colors = spec
+            .split(',')
+            .map(parse_color)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{} in palette {:?}", e, spec))?;
+        let colors = match colors[..] {
+            [background, foreground] => [background, foreground, foreground, foreground],
+            [background, plane1, plane2, both] => [background, plane1, plane2, both],
+            _ => {
+                return Err(format!(
+                    "palette {:?} is neither one of {} nor 2 or 4 colours",
+                    spec,
+                    THEMES.map(|(name, _)| name).join(", ")
+                ))
+            }
+        };
+        Ok(Palette {
+            name: spec.to_string(),
+            colors,
+        })
+    }
+
+    /**
+     * The next built-in theme, or the first one for custom palettes.
+     */
+    pub fn next(&self) -> Palette {
+        let idx = THEMES
+            .iter()
+            .position(|(name, _)| *name == self.name)
+            .map_or(0, |idx| (idx + 1) % THEMES.len());
+        Palette::parse(THEMES[idx].0).unwrap()
+    }
+
+    pub fn color(&self, pixel: u8) -> Color {
+        self.colors[(pixel & 0b11) as usize]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::parse(THEMES[0].0).unwrap()
+    }
+}
+
+fn parse_color(hex: &str) -> Result<Color, String> {
+    let digits = hex.trim().trim_start_matches('#');
+    match u32::from_str_radix(digits, 16) {
+        Ok(value) if digits.len() == 6 => Ok(rgb(value)),
+        _ => Err(format!("invalid colour {:?}, expected #rrggbb", hex)),
+    }
+}
+
+#[cfg(test)]
+#[path = "./palettes_test.rs"]
+mod palettes_test;
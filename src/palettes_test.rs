@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use sdl2::pixels::Color;
+
+    use crate::palettes::Palette;
+
+    #[test]
+    fn test_parse_theme() {
+        let palette = Palette::parse("classic").unwrap();
+        assert_eq!(palette.color(0), Color::RGB(0, 0, 0));
+        assert_eq!(palette.color(1), Color::RGB(255, 255, 255));
+    }
+
+    #[test]
+    fn test_parse_two_colours_use_foreground_for_both_planes() {
+        let palette = Palette::parse("#102030,#ffb000").unwrap();
+        assert_eq!(palette.colors[0], Color::RGB(0x10, 0x20, 0x30));
+        assert_eq!(palette.colors[1..], [Color::RGB(0xff, 0xb0, 0x00); 3]);
+    }
+
+    #[test]
+    fn test_parse_four_colours() {
+        let palette = Palette::parse("#000000,#ff0000,#00ff00,#0000ff").unwrap();
+        assert_eq!(palette.color(3), Color::RGB(0, 0, 255));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Palette::parse("sepia").is_err());
+        assert!(Palette::parse("#000000,#fff").is_err());
+        assert!(Palette::parse("#000000,#ffffff,#ffffff").is_err());
+    }
+
+    #[test]
+    fn test_next_cycles_through_themes() {
+        let first = Palette::default();
+        let mut palette = first.next();
+        assert_ne!(palette, first);
+        for _ in 0..10 {
+            if palette == first {
+                return;
+            }
+            palette = palette.next();
+        }
+        panic!("cycling never returned to the first theme");
+    }
+
+    #[test]
+    fn test_next_of_custom_palette_is_first_theme() {
+        let custom = Palette::parse("#000000,#ffffff").unwrap();
+        assert_eq!(custom.next(), Palette::default());
+    }
+}
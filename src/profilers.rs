@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::interpreters::{disassemble, opcode_name};
+use crate::memory::Memory;
+
+/**
+ * What an instruction does to the call tree and draw counts, as the interpreter decodes
+ * it.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Flow {
+    Call(u16),
+    Return,
+    Draw,
+    Other,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Subroutine {
+    calls: u64,
+    instructions: u64,
+}
+
+/**
+ * Counts executions per address and per opcode kind, time spent in each subroutine and
+ * draw calls per frame. Time is measured in executed instructions, which unlike wall clock
+ * time does not depend on how fast the host is.
+ */
+pub struct Profiler {
+    instructions: u64,
+    address_hits: HashMap<u16, u64>,
+    opcode_hits: HashMap<&'static str, u64>,
+    subroutines: HashMap<u16, Subroutine>,
+    // (subroutine address, instruction count when it was called)
+    call_stack: Vec<(u16, u64)>,
+    draws_this_frame: u32,
+    // number of frames that issued a given number of draw calls
+    draws_per_frame: HashMap<u32, u64>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            instructions: 0,
+            address_hits: HashMap::new(),
+            opcode_hits: HashMap::new(),
+            subroutines: HashMap::new(),
+            call_stack: Vec::new(),
+            draws_this_frame: 0,
+            draws_per_frame: HashMap::new(),
+        }
+    }
+
+    /**
+     * Record that the instruction `raw_opcode` at `pc` is about to be executed.
+     */
+    pub fn record(&mut self, pc: u16, raw_opcode: u16, flow: Flow) {
+        self.instructions += 1;
+        *self.address_hits.entry(pc).or_insert(0) += 1;
+        let name = opcode_name(raw_opcode).unwrap_or("invalid");
+        *self.opcode_hits.entry(name).or_insert(0) += 1;
+
+        match flow {
+            Flow::Call(addr) => {
+                self.subroutines.entry(addr).or_default().calls += 1;
+                self.call_stack.push((addr, self.instructions));
+            }
+            Flow::Return => {
+                if let Some((addr, called_at)) = self.call_stack.pop() {
+                    self.subroutines.entry(addr).or_default().instructions +=
+                        self.instructions - called_at;
+                }
+            }
+            Flow::Draw => self.draws_this_frame += 1,
+            Flow::Other => {}
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        *self
+            .draws_per_frame
+            .entry(self.draws_this_frame)
+            .or_insert(0) += 1;
+        self.draws_this_frame = 0;
+    }
+
+    /**
+     * Formats the profile sorted by hotspot, annotating each address with its disassembly.
+     */
+    pub fn report(&self, memory: &Memory) -> String {
+        let mut out = String::new();
+        let total = self.instructions.max(1) as f64;
+        let percent = |n: u64| 100.0 * n as f64 / total;
+
+        writeln!(out, "{} instructions executed", self.instructions).unwrap();
+
+        writeln!(out, "\nHotspots\n  addr    hits       %  instruction").unwrap();
+        let mut addresses: Vec<(&u16, &u64)> = self.address_hits.iter().collect();
+        addresses.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (addr, hits) in addresses {
+            let instruction = disassemble(memory.read_instruction(*addr));
+            writeln!(
+                out,
+                "  {:#05x} {:>8} {:>6.2}%  {}",
+                addr,
+                hits,
+                percent(*hits),
+                instruction
+            )
+            .unwrap();
+        }
+
+        writeln!(out, "\nOpcodes\n  {:<16} {:>8}       %", "kind", "count").unwrap();
+        let mut opcodes: Vec<(&&str, &u64)> = self.opcode_hits.iter().collect();
+        opcodes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (name, hits) in opcodes {
+            writeln!(out, "  {:<16} {:>8} {:>6.2}%", name, hits, percent(*hits)).unwrap();
+        }
+
+        // subroutines that have not returned yet still count up to now
+        let mut subroutines = self.subroutines.clone();
+        for (addr, called_at) in &self.call_stack {
+            subroutines.entry(*addr).or_default().instructions += self.instructions - called_at;
+        }
+        writeln!(
+            out,
+            "\nSubroutines (inclusive time)\n  addr    calls  instructions       %  per call"
+        )
+        .unwrap();
+        let mut subroutines: Vec<(u16, Subroutine)> = subroutines.into_iter().collect();
+        subroutines.sort_by(|a, b| b.1.instructions.cmp(&a.1.instructions).then(a.0.cmp(&b.0)));
+        for (addr, sub) in subroutines {
+            writeln!(
+                out,
+                "  {:#05x} {:>7} {:>13} {:>6.2}% {:>9.1}",
+                addr,
+                sub.calls,
+                sub.instructions,
+                percent(sub.instructions),
+                sub.instructions as f64 / sub.calls.max(1) as f64
+            )
+            .unwrap();
+        }
+
+        let frames: u64 = self.draws_per_frame.values().sum();
+        let draws: u64 = self
+            .draws_per_frame
+            .iter()
+            .map(|(draws, frames)| *draws as u64 * frames)
+            .sum();
+        let max = self.draws_per_frame.keys().max().copied().unwrap_or(0);
+        writeln!(
+            out,
+            "\nDraw calls per frame\n  {} frames, {:.2} average, {} max",
+            frames,
+            draws as f64 / frames.max(1) as f64,
+            max
+        )
+        .unwrap();
+        let mut histogram: Vec<(&u32, &u64)> = self.draws_per_frame.iter().collect();
+        histogram.sort();
+        for (draws, frames) in histogram {
+            writeln!(out, "  {:>4} draws: {} frames", draws, frames).unwrap();
+        }
+
+        out
+    }
+}
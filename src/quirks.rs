@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+/**
+ * Behaviours that differ between CHIP-8 interpreters and that games rely on. All off is
+ * how this emulator always behaved.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Quirks {
+    // 8XY6 and 8XYE shift VY into VX instead of shifting VX in place
+    pub shift: bool,
+    // FX55 and FX65 leave I pointing past the last register stored or loaded
+    pub load_store: bool,
+    // BXNN jumps to XNN plus VX instead of BNNN jumping to NNN plus V0
+    pub jump: bool,
+    // 8XY1, 8XY2 and 8XY3 reset VF
+    pub vf_reset: bool,
+    // sprites are cut off at the edges of the screen instead of wrapping around
+    pub clip: bool,
+}
+
+const PRESETS: [(&str, Quirks); 2] = [
+    (
+        "vip",
+        Quirks {
+            shift: true,
+            load_store: true,
+            jump: false,
+            vf_reset: true,
+            clip: true,
+        },
+    ),
+    (
+        "schip",
+        Quirks {
+            shift: false,
+            load_store: false,
+            jump: true,
+            vf_reset: false,
+            clip: true,
+        },
+    ),
+];
+
+const NAMES: [&str; 5] = ["shift", "load-store", "jump", "vf-reset", "clip"];
+
+impl Quirks {
+    /**
+     * Parses a preset, `vip` for the COSMAC VIP's original interpreter or `schip` for
+     * SUPER-CHIP, or a comma separated list of the quirks to turn on, e.g. `shift,clip`.
+     * `none` turns them all off.
+     */
+    pub fn parse(spec: &str) -> Result<Quirks, String> {
+        if let Some((_, quirks)) = PRESETS.iter().find(|(name, _)| *name == spec) {
+            return Ok(*quirks);
+        }
+        let mut quirks = Quirks::default();
+        for name in spec.split(',').map(str::trim) {
+            match name {
+                "none" => (),
+                "shift" => quirks.shift = true,
+                "load-store" => quirks.load_store = true,
+                "jump" => quirks.jump = true,
+                "vf-reset" => quirks.vf_reset = true,
+                "clip" => quirks.clip = true,
+                _ => {
+                    return Err(format!(
+                        "unknown quirk {:?}, expected vip, schip, none or some of {}",
+                        name,
+                        NAMES.join(", ")
+                    ))
+                }
+            }
+        }
+        Ok(quirks)
+    }
+}
+
+#[cfg(test)]
+#[path = "./quirks_test.rs"]
+mod quirks_test;
@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod tests {
+    use crate::quirks::Quirks;
+
+    #[test]
+    fn test_parse_preset() {
+        let quirks = Quirks::parse("vip").unwrap();
+        assert!(quirks.shift && quirks.load_store && quirks.vf_reset && quirks.clip);
+        assert!(!quirks.jump);
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let quirks = Quirks::parse("shift, clip").unwrap();
+        assert_eq!(
+            quirks,
+            Quirks {
+                shift: true,
+                clip: true,
+                ..Quirks::default()
+            }
+        );
+        assert_eq!(Quirks::parse("none").unwrap(), Quirks::default());
+        assert!(Quirks::parse("shift,wrap").is_err());
+    }
+}
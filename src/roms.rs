@@ -0,0 +1,204 @@
+use std::fs;
+use std::io::{self, BufRead, Cursor, IsTerminal, Read, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+use crate::databases::Platform;
+use crate::memory::MEM_SIZE;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+// what ROMs in archives are usually called, to skip the readme and artwork next to them
+const ROM_EXTENSIONS: [&str; 6] = ["ch8", "c8", "sc8", "xo8", "hc8", "c8x"];
+// anything bigger can't be a ROM for a 4K machine, even gzipped
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/**
+ * A ROM and the name of the file it came from, which the config uses to tell ROMs apart.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rom {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+impl Rom {
+    /**
+     * Reads a ROM from a file, or from stdin if `path` is `-`. See `Rom::from_bytes` for
+     * archives and `entry`.
+     */
+    pub fn read(path: &str, entry: Option<&str>) -> Result<Rom, String> {
+        let mut bytes = vec![];
+        if path == "-" {
+            io::stdin()
+                .take(MAX_FILE_SIZE)
+                .read_to_end(&mut bytes)
+                .map_err(|e| format!("stdin: {}", e))?;
+            return Rom::from_bytes("stdin", &bytes, entry);
+        }
+        fs::File::open(path)
+            .and_then(|file| file.take(MAX_FILE_SIZE).read_to_end(&mut bytes))
+            .map_err(|e| format!("{}: {}", path, e))?;
+        let name = Path::new(path)
+            .file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+        Rom::from_bytes(&name, &bytes, entry).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /**
+     * A ROM from bytes, which can also be gzipped or a zip archive. `entry` picks the ROM
+     * in an archive holding several; without it the user is asked which one to play.
+     */
+    pub fn from_bytes(name: &str, bytes: &[u8], entry: Option<&str>) -> Result<Rom, String> {
+        if entry.is_some() && !bytes.starts_with(ZIP_MAGIC) {
+            return Err("an entry can only be picked from a zip archive".to_string());
+        }
+        if bytes.starts_with(GZIP_MAGIC) {
+            let mut rom = vec![];
+            GzDecoder::new(bytes)
+                .take(MAX_FILE_SIZE)
+                .read_to_end(&mut rom)
+                .map_err(|e| format!("invalid gzip file: {}", e))?;
+            let name = name.strip_suffix(".gz").unwrap_or(name);
+            return Ok(Rom {
+                name: name.to_string(),
+                bytes: rom,
+            });
+        }
+        if bytes.starts_with(ZIP_MAGIC) {
+            return Rom::from_zip(bytes, entry, prompt);
+        }
+        Ok(Rom {
+            name: name.to_string(),
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    /**
+     * The ROM in a zip archive: `entry` if given, the only ROM if there's one, or the
+     * one `choose` picks by index from the names of several.
+     */
+    fn from_zip(
+        bytes: &[u8],
+        entry: Option<&str>,
+        choose: impl FnOnce(&[String]) -> Result<usize, String>,
+    ) -> Result<Rom, String> {
+        let mut archive =
+            ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("invalid zip file: {}", e))?;
+        // in archive order, which `file_names` doesn't keep
+        let mut files = vec![];
+        for idx in 0..archive.len() {
+            let file = archive.by_index_raw(idx).map_err(|e| e.to_string())?;
+            if !file.is_dir() {
+                files.push(file.name().to_string());
+            }
+        }
+        let roms: Vec<String> = files.iter().filter(|name| is_rom(name)).cloned().collect();
+        // archives of ROMs with unusual extensions
+        let roms = if roms.is_empty() { files } else { roms };
+
+        let name = match entry {
+            Some(entry) => roms
+                .iter()
+                .find(|name| *name == entry || file_name(name) == entry)
+                .cloned()
+                .ok_or(format!(
+                    "there's no {} in the archive, it has {}",
+                    entry,
+                    roms.join(", ")
+                ))?,
+            None => match roms.len() {
+                0 => return Err("the archive is empty".to_string()),
+                1 => roms[0].clone(),
+                _ => {
+                    let idx = choose(&roms)?;
+                    roms.get(idx).cloned().ok_or("no such ROM")?
+                }
+            },
+        };
+        let mut rom = vec![];
+        archive
+            .by_name(&name)
+            .map_err(|e| e.to_string())?
+            .take(MAX_FILE_SIZE)
+            .read_to_end(&mut rom)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        Ok(Rom {
+            name: file_name(&name).to_string(),
+            bytes: rom,
+        })
+    }
+
+    /**
+     * Fails if the ROM doesn't fit in memory from `start`, or in what the platform it's
+     * played as leaves for programs.
+     */
+    pub fn check_size(&self, start: u16, platform: Option<&Platform>) -> Result<(), String> {
+        let (end, machine) = match platform {
+            Some(platform) if platform.memory_end() < MEM_SIZE => {
+                (platform.memory_end(), platform.name.as_str())
+            }
+            _ => (MEM_SIZE, "4 KB of memory"),
+        };
+        let max_size = end.saturating_sub(start as usize);
+        if self.bytes.len() > max_size {
+            return Err(format!(
+                "{} is {} bytes, but only {} bytes fit from {:#05x} on {}",
+                self.name,
+                self.bytes.len(),
+                max_size,
+                start,
+                machine
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn is_rom(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ROM_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+}
+
+/**
+ * Asks on the terminal which of several ROMs to play, or fails listing them if there's
+ * nobody to ask.
+ */
+fn prompt(names: &[String]) -> Result<usize, String> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(format!(
+            "the archive has several ROMs, pick one as the entry: {}",
+            names.join(", ")
+        ));
+    }
+    let mut stderr = io::stderr();
+    for (idx, name) in names.iter().enumerate() {
+        let _ = writeln!(stderr, "{:>3}. {}", idx + 1, name);
+    }
+    let _ = write!(stderr, "which ROM? ");
+    let _ = stderr.flush();
+    let mut answer = String::new();
+    stdin
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| e.to_string())?;
+    answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|number| (1..=names.len()).contains(number))
+        .map(|number| number - 1)
+        .ok_or(format!("no ROM number {:?}", answer.trim()))
+}
+
+#[cfg(test)]
+#[path = "./roms_test.rs"]
+mod roms_test;
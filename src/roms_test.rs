@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    use crate::databases::Database;
+    use crate::roms::Rom;
+
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        writer
+            .add_directory("roms/", FileOptions::default())
+            .unwrap();
+        for (name, bytes) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(bytes).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn rom(name: &str, bytes: &[u8]) -> Rom {
+        Rom {
+            name: name.to_string(),
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_plain_bytes() {
+        let loaded = Rom::from_bytes("pong.ch8", &[0x12, 0x00], None).unwrap();
+        assert_eq!(loaded, rom("pong.ch8", &[0x12, 0x00]));
+        assert!(Rom::from_bytes("pong.ch8", &[0x12, 0x00], Some("pong.ch8")).is_err());
+    }
+
+    #[test]
+    fn test_gzip() {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&[0x00, 0xE0, 0x12, 0x02]).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        let loaded = Rom::from_bytes("pong.ch8.gz", &gzipped, None).unwrap();
+        assert_eq!(loaded, rom("pong.ch8", &[0x00, 0xE0, 0x12, 0x02]));
+        assert_eq!(
+            Rom::from_bytes("pong.ch8.gz", &gzipped, Some("pong.ch8")),
+            Err("an entry can only be picked from a zip archive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_zip_with_one_rom() {
+        let archive = zip(&[("README.txt", b"hello"), ("roms/pong.ch8", &[0x12, 0x00])]);
+        let loaded = Rom::from_bytes("pong.zip", &archive, None).unwrap();
+        assert_eq!(loaded, rom("pong.ch8", &[0x12, 0x00]));
+    }
+
+    #[test]
+    fn test_zip_with_several_roms() {
+        let archive = zip(&[("tetris.ch8", &[0x12, 0x02]), ("pong.ch8", &[0x12, 0x00])]);
+        let loaded = Rom::from_bytes("games.zip", &archive, Some("tetris.ch8")).unwrap();
+        assert_eq!(loaded, rom("tetris.ch8", &[0x12, 0x02]));
+        assert!(Rom::from_bytes("games.zip", &archive, Some("snake.ch8")).is_err());
+
+        // offered in archive order
+        let chosen = Rom::from_zip(&archive, None, |names| {
+            assert_eq!(names, ["tetris.ch8", "pong.ch8"]);
+            Ok(1)
+        })
+        .unwrap();
+        assert_eq!(chosen, rom("pong.ch8", &[0x12, 0x00]));
+        assert!(Rom::from_zip(&archive, None, |_| Ok(2)).is_err());
+    }
+
+    #[test]
+    fn test_check_size() {
+        let database = Database::embedded();
+        let vip = database.platform("originalChip8").unwrap();
+        let modern = database.platform("modernChip8").unwrap();
+
+        let fits = rom("big.ch8", &[0; 3584]);
+        assert_eq!(fits.check_size(0x200, None), Ok(()));
+        assert_eq!(fits.check_size(0x200, Some(modern)), Ok(()));
+        assert_eq!(
+            fits.check_size(0x200, Some(vip)),
+            Err(format!(
+                "big.ch8 is 3584 bytes, but only 3232 bytes fit from 0x200 on {}",
+                vip.name
+            ))
+        );
+        assert_eq!(
+            fits.check_size(0x300, None),
+            Err(
+                "big.ch8 is 3584 bytes, but only 3328 bytes fit from 0x300 on 4 KB of memory"
+                    .to_string()
+            )
+        );
+        assert!(rom("huge.ch8", &[0; 3585]).check_size(0x200, None).is_err());
+
+        // loaded higher up, less of what the platform leaves fits
+        let vip_sized = rom("vip.ch8", &[0; 3232]);
+        assert_eq!(vip_sized.check_size(0x200, Some(vip)), Ok(()));
+        assert_eq!(
+            vip_sized.check_size(0x300, Some(vip)),
+            Err(format!(
+                "vip.ch8 is 3232 bytes, but only 2976 bytes fit from 0x300 on {}",
+                vip.name
+            ))
+        );
+    }
+}
@@ -0,0 +1,527 @@
+use std::env;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use sdl2::keyboard::Scancode;
+
+use crate::beepers::Beeper;
+use crate::displays::{Drawable, GamePixels};
+use crate::input_driver::{EventPollable, Hotkey, InputEvent, TimedEvent};
+use crate::keymaps::Keymap;
+use crate::palettes::Palette;
+
+// terminal pixels per CHIP-8 pixel for the graphics protocols
+const GRAPHICS_SCALE: usize = 8;
+
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l\x1b[2J";
+const LEAVE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TerminalGraphics {
+    // one character per 1x2 pixels using `▀` with separate fore- and background colours
+    HalfBlock,
+    // one braille character per 2x4 pixels
+    Braille,
+    Sixel,
+    Kitty,
+}
+
+impl TerminalGraphics {
+    /**
+     * Parses `halfblock`, `braille`, `sixel`, `kitty` or `auto`, which picks the kitty
+     * graphics protocol when running in kitty and half blocks otherwise.
+     */
+    pub fn parse(name: &str) -> Result<TerminalGraphics, String> {
+        match name {
+            "halfblock" => Ok(TerminalGraphics::HalfBlock),
+            "braille" => Ok(TerminalGraphics::Braille),
+            "sixel" => Ok(TerminalGraphics::Sixel),
+            "kitty" => Ok(TerminalGraphics::Kitty),
+            "auto" => Ok(TerminalGraphics::detect()),
+            _ => Err(format!(
+                "unknown terminal graphics {:?}, expected halfblock, braille, sixel, kitty or auto",
+                name
+            )),
+        }
+    }
+
+    fn detect() -> TerminalGraphics {
+        let term = env::var("TERM").unwrap_or_default();
+        if env::var("KITTY_WINDOW_ID").is_ok() || term.contains("kitty") {
+            TerminalGraphics::Kitty
+        } else {
+            TerminalGraphics::HalfBlock
+        }
+    }
+}
+
+/**
+ * Renders the game into the terminal, e.g. to play over SSH.
+ */
+pub struct TerminalDisplay {
+    pixels: GamePixels,
+    graphics: TerminalGraphics,
+    palette: Palette,
+    // whether the pixels changed since they were last written to the terminal
+    dirty: bool,
+}
+
+impl TerminalDisplay {
+    pub fn new(graphics: TerminalGraphics, palette: Palette) -> Result<TerminalDisplay, String> {
+        let mut stdout = io::stdout();
+        stdout
+            .write_all(ENTER_SCREEN.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|e| e.to_string())?;
+        Ok(TerminalDisplay {
+            pixels: GamePixels::new(),
+            graphics,
+            palette,
+            dirty: true,
+        })
+    }
+
+    fn draw_pixels(&self) {
+        let frame = match self.graphics {
+            TerminalGraphics::HalfBlock => half_blocks(&self.pixels, &self.palette),
+            TerminalGraphics::Braille => braille(&self.pixels, &self.palette),
+            TerminalGraphics::Sixel => sixel(&self.pixels, &self.palette, GRAPHICS_SCALE),
+            TerminalGraphics::Kitty => kitty(&self.pixels, &self.palette),
+        };
+        let mut stdout = io::stdout().lock();
+        // the game keeps running if the terminal went away
+        let _ = write!(stdout, "\x1b[H{}", frame).and_then(|_| stdout.flush());
+    }
+}
+
+impl Drop for TerminalDisplay {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = stdout
+            .write_all(LEAVE_SCREEN.as_bytes())
+            .and_then(|_| stdout.flush());
+    }
+}
+
+impl Drawable for TerminalDisplay {
+    fn draw_at(&mut self, bytes: &[u8], start_coord: (usize, usize)) -> bool {
+        let collided = self.pixels.fill_in_bytes(bytes, start_coord);
+        self.dirty = true;
+        collided
+    }
+
+    fn clear(&mut self) {
+        self.pixels = GamePixels::new();
+        self.dirty = true;
+    }
+
+    fn pixels(&self) -> &GamePixels {
+        &self.pixels
+    }
+
+    fn restore(&mut self, pixels: GamePixels) {
+        self.pixels = pixels;
+        self.dirty = true;
+    }
+
+    fn cycle_palette(&mut self) {
+        self.palette = self.palette.next();
+        self.dirty = true;
+    }
+
+    fn set_title(&mut self, title: &str) {
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "\x1b]0;{} - Chip8\x07", title).and_then(|_| stdout.flush());
+    }
+
+    fn present(&mut self) {
+        if self.dirty {
+            self.draw_pixels();
+            self.dirty = false;
+        }
+    }
+}
+
+fn half_blocks(pixels: &GamePixels, palette: &Palette) -> String {
+    let mut out = String::new();
+    for rows in pixels.value.chunks(2) {
+        let mut last = None;
+        for (top, bottom) in rows[0].iter().zip(rows[1].iter()) {
+            if last != Some((top, bottom)) {
+                let (fg, bg) = (palette.color(*top), palette.color(*bottom));
+                out += &format!(
+                    "\x1b[38;2;{};{};{};48;2;{};{};{}m",
+                    fg.r, fg.g, fg.b, bg.r, bg.g, bg.b
+                );
+                last = Some((top, bottom));
+            }
+            out.push('▀');
+        }
+        out += "\x1b[0m\r\n";
+    }
+    out
+}
+
+// braille dot bits for the pixel at (x, y) within a 2x4 cell
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+// braille dots have a single colour, so any set pixel uses the first bitplane's
+fn braille(pixels: &GamePixels, palette: &Palette) -> String {
+    let (fg, bg) = (palette.color(1), palette.color(0));
+    let mut out = format!(
+        "\x1b[38;2;{};{};{};48;2;{};{};{}m",
+        fg.r, fg.g, fg.b, bg.r, bg.g, bg.b
+    );
+    for rows in pixels.value.chunks(4) {
+        for x in (0..rows[0].len()).step_by(2) {
+            let mut dots = 0;
+            for (dy, row) in rows.iter().enumerate() {
+                for dx in 0..2 {
+                    if row[x + dx] != 0 {
+                        dots |= BRAILLE_DOTS[dy][dx];
+                    }
+                }
+            }
+            out.push(char::from_u32(0x2800 + dots).unwrap());
+        }
+        out += "\r\n";
+    }
+    out + "\x1b[0m"
+}
+
+fn sixel(pixels: &GamePixels, palette: &Palette, scale: usize) -> String {
+    let height = pixels.value.len() * scale;
+    let width = pixels.value[0].len() * scale;
+    let percent = |c: u8| c as u32 * 100 / 255;
+    let mut out = format!("\x1bPq\"1;1;{};{}", width, height);
+    for (idx, color) in palette.colors.iter().enumerate() {
+        out += &format!(
+            "#{};2;{};{};{}",
+            idx,
+            percent(color.r),
+            percent(color.g),
+            percent(color.b)
+        );
+    }
+    for band in (0..height).step_by(6) {
+        for color in 0..palette.colors.len() as u8 {
+            out += &format!("#{}", color);
+            let columns = (0..width).map(|x| {
+                let bits = (0..6)
+                    .filter(|dy| band + dy < height)
+                    .filter(|dy| pixels.value[(band + dy) / scale][x / scale] == color)
+                    .fold(0, |bits, dy| bits | 1 << dy);
+                (63 + bits) as u8 as char
+            });
+            push_run_length_encoded(&mut out, columns);
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out + "\x1b\\"
+}
+
+fn push_run_length_encoded(out: &mut String, chars: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |out: &mut String, (c, n): (char, usize)| {
+        if n > 3 {
+            *out += &format!("!{}{}", n, c);
+        } else {
+            out.extend(std::iter::repeat_n(c, n));
+        }
+    };
+    for c in chars {
+        run = match run {
+            Some((last, n)) if last == c => Some((last, n + 1)),
+            Some(previous) => {
+                flush(out, previous);
+                Some((c, 1))
+            }
+            None => Some((c, 1)),
+        };
+    }
+    if let Some(run) = run {
+        flush(out, run);
+    }
+}
+
+fn kitty(pixels: &GamePixels, palette: &Palette) -> String {
+    let height = pixels.value.len();
+    let width = pixels.value[0].len();
+    let rgb: Vec<u8> = pixels
+        .value
+        .iter()
+        .flatten()
+        .flat_map(|bit| {
+            let color = palette.color(*bit);
+            [color.r, color.g, color.b]
+        })
+        .collect();
+    let data = base64(&rgb);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    let mut out = String::new();
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let more = (idx + 1 < chunks.len()) as u8;
+        if idx == 0 {
+            // transmit and display as image and placement 1 stretched over 2 columns and
+            // 1 row per 2x2 pixels, replacing the previous frame
+            out += &format!(
+                "\x1b_Gf=24,s={},v={},a=T,i=1,p=1,c={},r={},q=2,m={};",
+                width,
+                height,
+                width * 2,
+                height / 2,
+                more
+            );
+        } else {
+            out += &format!("\x1b_Gm={};", more);
+        }
+        out += std::str::from_utf8(chunk).unwrap();
+        out += "\x1b\\";
+    }
+    out
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * idx) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/**
+ * Reads keys from a terminal in raw mode. Terminals only report key presses (and their
+ * auto repeat), so a key counts as held until `release_after` passed without it being
+ * reported again.
+ */
+pub struct TerminalInput {
+    original: libc::termios,
+    pressed_at: [Option<Instant>; 16],
+    release_after: Duration,
+    keymap: Keymap,
+    tokenizer: Tokenizer,
+}
+
+impl TerminalInput {
+    pub fn new(release_after: Duration, keymap: Keymap) -> Result<TerminalInput, String> {
+        // SAFETY: termios is plain data filled in by tcgetattr, and stdin stays open for the
+        // lifetime of the process.
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err("stdin is not a terminal".to_string());
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            // let reads return immediately even if no key was pressed
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error().to_string());
+            }
+            Ok(TerminalInput {
+                original,
+                pressed_at: [None; 16],
+                release_after,
+                keymap,
+                tokenizer: Tokenizer::new(),
+            })
+        }
+    }
+
+    fn read_available(&self) -> Vec<u8> {
+        let mut buffer = [0u8; 64];
+        // SAFETY: reading at most `buffer.len()` bytes into `buffer`.
+        let read = unsafe {
+            libc::read(
+                libc::STDIN_FILENO,
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+            )
+        };
+        buffer[..read.max(0) as usize].to_vec()
+    }
+}
+
+impl Drop for TerminalInput {
+    fn drop(&mut self) {
+        // SAFETY: restores the settings read in `new`.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+// Ctrl-C, as raw mode no longer turns it into a signal
+const QUIT_BYTE: u8 = 0x03;
+const ESCAPE_BYTE: u8 = 0x1b;
+// how long to wait for the rest of an escape sequence before taking the Escape for the key,
+// as over SSH the bytes of an arrow key can arrive in separate reads
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(100);
+
+// F2 as sent by xterm and by rxvt
+const CYCLE_PALETTE_SEQUENCES: [&[u8]; 2] = [b"\x1bOQ", b"\x1b[12~"];
+
+/**
+ * Splits terminal input into single bytes and escape sequences like the ones sent for
+ * function and arrow keys, so their letters aren't taken for keypad keys. Also returns an
+ * escape sequence cut off at the end, whose rest may still arrive.
+ */
+fn split_input(bytes: &[u8]) -> (Vec<&[u8]>, &[u8]) {
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let len = match bytes[idx..] {
+            [ESCAPE_BYTE] | [ESCAPE_BYTE, b'O'] => break,
+            [ESCAPE_BYTE, b'O', _, ..] => 3,
+            [ESCAPE_BYTE, b'[', ..] => match bytes[idx + 2..]
+                .iter()
+                .position(|byte| (0x40..=0x7e).contains(byte))
+            {
+                Some(end) => end + 3,
+                None => break,
+            },
+            _ => 1,
+        };
+        tokens.push(&bytes[idx..idx + len]);
+        idx += len;
+    }
+    (tokens, &bytes[idx..])
+}
+
+/**
+ * Turns reads from the terminal into tokens, holding back an escape sequence split across
+ * reads until the rest arrives. A lone Escape only counts as the key once nothing followed
+ * it for `ESCAPE_TIMEOUT`.
+ */
+struct Tokenizer {
+    pending: Vec<u8>,
+    // when bytes were last read
+    read_at: Option<Instant>,
+}
+
+impl Tokenizer {
+    fn new() -> Tokenizer {
+        Tokenizer {
+            pending: vec![],
+            read_at: None,
+        }
+    }
+
+    fn feed(&mut self, bytes: &[u8], now: Instant) -> Vec<Vec<u8>> {
+        if !bytes.is_empty() {
+            self.read_at = Some(now);
+        }
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(bytes);
+        let (tokens, rest) = split_input(&input);
+        let mut tokens: Vec<Vec<u8>> = tokens.into_iter().map(<[u8]>::to_vec).collect();
+        let timed_out = self
+            .read_at
+            .is_some_and(|read_at| now - read_at >= ESCAPE_TIMEOUT);
+        if !timed_out {
+            self.pending = rest.to_vec();
+        } else if rest == [ESCAPE_BYTE] {
+            // anything else is a sequence that was cut off for good
+            tokens.push(rest.to_vec());
+        }
+        tokens
+    }
+}
+
+/**
+ * The CHIP-8 keys a typed character presses, taking the character for the key it is on in
+ * the US layout as terminals don't report scancodes.
+ */
+fn keys_for_byte(keymap: &Keymap, byte: u8) -> Vec<u8> {
+    let name = (byte as char).to_ascii_uppercase().to_string();
+    Scancode::from_name(&name).map_or(vec![], |scancode| keymap.chip8_keys(scancode).collect())
+}
+
+impl EventPollable for TerminalInput {
+    fn poll(&mut self, now: u64) -> Vec<TimedEvent> {
+        let mut events = Vec::new();
+        let mut push = |event| events.push(TimedEvent { at: now, event });
+        let time = Instant::now();
+        let input = self.read_available();
+        for token in self.tokenizer.feed(&input, time) {
+            match token.as_slice() {
+                [QUIT_BYTE] => push(InputEvent::Quit),
+                [ESCAPE_BYTE] => push(InputEvent::Hotkey(Hotkey::Quit, true)),
+                [byte] => {
+                    for key in keys_for_byte(&self.keymap, *byte) {
+                        // typing a held key again is the terminal repeating it
+                        if self.pressed_at[key as usize].is_none() {
+                            push(InputEvent::KeyDown(key));
+                        }
+                        self.pressed_at[key as usize] = Some(time);
+                    }
+                }
+                sequence if CYCLE_PALETTE_SEQUENCES.contains(&sequence) => {
+                    push(InputEvent::Hotkey(Hotkey::CyclePalette, true))
+                }
+                _ => (),
+            }
+        }
+
+        for (key, pressed_at) in self.pressed_at.iter_mut().enumerate() {
+            if pressed_at.is_some_and(|at| time - at > self.release_after) {
+                *pressed_at = None;
+                push(InputEvent::KeyUp(key as u8));
+            }
+        }
+        events
+    }
+}
+
+/**
+ * Rings the terminal bell when the sound starts.
+ */
+pub struct TerminalBell {
+    sounding: bool,
+    muted: bool,
+}
+
+impl TerminalBell {
+    pub fn new() -> TerminalBell {
+        TerminalBell {
+            sounding: false,
+            muted: false,
+        }
+    }
+}
+
+impl Default for TerminalBell {
+    fn default() -> Self {
+        TerminalBell::new()
+    }
+}
+
+impl Beeper for TerminalBell {
+    fn play_frame(&mut self, sounding: bool) {
+        if sounding && !self.sounding && !self.muted {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        }
+        self.sounding = sounding;
+    }
+
+    fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+}
+
+#[cfg(test)]
+#[path = "./terminals_test.rs"]
+mod terminals_test;
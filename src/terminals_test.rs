@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::terminals::{split_input, Tokenizer, ESCAPE_TIMEOUT};
+
+    #[test]
+    fn test_split_input() {
+        let (tokens, rest) = split_input(b"w\x1b[Aq\x1bOQ\x1b[");
+        assert_eq!(tokens, [&b"w"[..], b"\x1b[A", b"q", b"\x1bOQ"]);
+        assert_eq!(rest, b"\x1b[");
+    }
+
+    #[test]
+    fn test_arrow_key_split_across_reads() {
+        let mut tokenizer = Tokenizer::new();
+        let start = Instant::now();
+        assert!(tokenizer.feed(b"\x1b", start).is_empty());
+        let later = start + Duration::from_millis(20);
+        assert_eq!(tokenizer.feed(b"[A", later), [b"\x1b[A".to_vec()]);
+        assert!(tokenizer.feed(b"", later + ESCAPE_TIMEOUT).is_empty());
+    }
+
+    #[test]
+    fn test_lone_escape_after_timeout() {
+        let mut tokenizer = Tokenizer::new();
+        let start = Instant::now();
+        assert!(tokenizer.feed(b"\x1b", start).is_empty());
+        assert!(tokenizer.feed(b"", start + ESCAPE_TIMEOUT / 2).is_empty());
+        assert_eq!(tokenizer.feed(b"", start + ESCAPE_TIMEOUT), [vec![0x1b]]);
+        assert!(tokenizer.feed(b"", start + 2 * ESCAPE_TIMEOUT).is_empty());
+    }
+}
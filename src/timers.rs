@@ -1,17 +1,28 @@
-use crate::waves::Audio;
+use crate::beepers::Beeper;
+use crate::configs::Settings;
 
 pub trait TimerActions {
     fn set(&mut self, value: u8);
     fn get(&self) -> u8;
     fn decrement(&mut self);
+    /*
+     * Silence or unsilence the beep, for timers that make a sound.
+     */
+    fn toggle_mute(&mut self) {}
+    /*
+     * Fill in how the sound is currently set up, for saving it to the config file.
+     */
+    fn record_settings(&self, _settings: &mut Settings) {}
 }
 
-impl TimerActions for SoundTimer {
+impl<B: Beeper> TimerActions for SoundTimer<B> {
     fn decrement(&mut self) {
+        // the frame that just ended beeps if the timer was set during it, so that setting
+        // it to N beeps for exactly N frames
+        self.beeper.play_frame(self.value > 0);
         if self.value > 0 {
             self.value -= 1;
         }
-        self.action();
     }
 
     fn set(&mut self, value: u8) {
@@ -21,6 +32,15 @@ impl TimerActions for SoundTimer {
     fn get(&self) -> u8 {
         self.value
     }
+
+    fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.beeper.set_muted(self.muted);
+    }
+
+    fn record_settings(&self, settings: &mut Settings) {
+        settings.mute = Some(self.muted);
+    }
 }
 
 impl TimerActions for DelayTimer {
@@ -39,29 +59,37 @@ impl TimerActions for DelayTimer {
     }
 }
 
-pub struct SoundTimer {
+/**
+ * Beeps while it is above zero, on whichever beeper it was given.
+ */
+pub struct SoundTimer<B: Beeper> {
     pub(crate) value: u8,
-    audio: Audio,
-    sounding: bool,
+    beeper: B,
+    muted: bool,
 }
-impl SoundTimer {
-    pub fn new(audio: Audio) -> SoundTimer {
+impl<B: Beeper> SoundTimer<B> {
+    pub fn new(mut beeper: B, muted: bool) -> SoundTimer<B> {
+        beeper.set_muted(muted);
         SoundTimer {
             value: 0,
-            audio: audio,
-            sounding: false,
+            beeper,
+            muted,
         }
     }
-    fn action(&mut self) {
-        if self.value > 0 && !self.sounding {
-            self.sounding = true;
-            self.audio.sound();
-        } else if self.value <= 0 && self.sounding {
-            self.sounding = false;
-            self.audio.pause();
-        }
+    pub fn beeper(&self) -> &B {
+        &self.beeper
     }
 }
 pub struct DelayTimer {
     pub(crate) value: u8,
 }
+impl DelayTimer {
+    pub fn new() -> DelayTimer {
+        DelayTimer { value: 0 }
+    }
+}
+impl Default for DelayTimer {
+    fn default() -> Self {
+        DelayTimer::new()
+    }
+}
@@ -0,0 +1,177 @@
+use sdl2::rect::Rect;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Scaling {
+    // the largest whole multiple of the game's resolution that fits, for crisp pixels
+    Integer,
+    // as large as fits while keeping the aspect ratio
+    Fit,
+}
+
+// window pixels per game pixel when the window opens
+pub const DEFAULT_WINDOW_SCALE: u32 = 20;
+
+/**
+ * How the game's pixels are laid out in the window: scaled, letterboxed, rotated and
+ * flipped, e.g. for ROMs written for calculators held in portrait.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Viewport {
+    pub scaling: Scaling,
+    // clockwise in degrees, one of 0, 90, 180 or 270
+    pub rotation: u16,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    // lines between the pixels
+    pub grid: bool,
+    pub fullscreen: bool,
+    // window pixels per game pixel when the window opens, before it's resized
+    pub window_scale: u32,
+}
+
+impl Viewport {
+    pub fn new() -> Viewport {
+        Viewport {
+            scaling: Scaling::Integer,
+            rotation: 0,
+            flip_horizontal: false,
+            flip_vertical: false,
+            grid: false,
+            fullscreen: false,
+            window_scale: DEFAULT_WINDOW_SCALE,
+        }
+    }
+
+    pub fn parse_scaling(spec: &str) -> Result<Scaling, String> {
+        match spec {
+            "integer" => Ok(Scaling::Integer),
+            "fit" => Ok(Scaling::Fit),
+            _ => Err(format!(
+                "unknown scaling {:?}, expected integer or fit",
+                spec
+            )),
+        }
+    }
+
+    pub fn parse_rotation(spec: &str) -> Result<u16, String> {
+        match spec {
+            "0" | "90" | "180" | "270" => Ok(spec.parse().unwrap()),
+            _ => Err(format!(
+                "invalid rotation {:?}, expected 0, 90, 180 or 270",
+                spec
+            )),
+        }
+    }
+
+    pub fn scaling_name(&self) -> &'static str {
+        match self.scaling {
+            Scaling::Integer => "integer",
+            Scaling::Fit => "fit",
+        }
+    }
+
+    /**
+     * Parses `h`, `v`, `hv` or `none` into whether to flip horizontally and vertically.
+     */
+    pub fn parse_flip(spec: &str) -> Result<(bool, bool), String> {
+        match spec {
+            "none" => Ok((false, false)),
+            "h" => Ok((true, false)),
+            "v" => Ok((false, true)),
+            "hv" | "vh" => Ok((true, true)),
+            _ => Err(format!(
+                "invalid flip {:?}, expected h, v, hv or none",
+                spec
+            )),
+        }
+    }
+
+    /**
+     * The flips in the format of `parse_flip`.
+     */
+    pub fn flip_spec(&self) -> &'static str {
+        match (self.flip_horizontal, self.flip_vertical) {
+            (false, false) => "none",
+            (true, false) => "h",
+            (false, true) => "v",
+            (true, true) => "hv",
+        }
+    }
+
+    /**
+     * Rotates by another 90 degrees clockwise.
+     */
+    pub fn rotate(&mut self) {
+        self.rotation = (self.rotation + 90) % 360;
+    }
+
+    pub fn toggle_scaling(&mut self) {
+        self.scaling = match self.scaling {
+            Scaling::Integer => Scaling::Fit,
+            Scaling::Fit => Scaling::Integer,
+        };
+    }
+
+    /**
+     * The size of `(width, height)` pixels once rotated.
+     */
+    pub fn oriented(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        if self.rotation % 180 == 90 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /**
+     * How many window pixels one game pixel takes up.
+     */
+    pub fn scale(&self, window: (u32, u32), pixels: (u32, u32)) -> f64 {
+        let (width, height) = self.oriented(pixels);
+        let fit = f64::min(
+            window.0 as f64 / width as f64,
+            window.1 as f64 / height as f64,
+        );
+        match self.scaling {
+            Scaling::Integer => fit.floor().max(1.0),
+            Scaling::Fit => fit,
+        }
+    }
+
+    /**
+     * Where the picture of `pixels` shows up in `window`, centered with the rest left for
+     * letterboxing.
+     */
+    pub fn picture(&self, window: (u32, u32), pixels: (u32, u32)) -> Rect {
+        let scale = self.scale(window, pixels);
+        let (width, height) = self.oriented(pixels);
+        let width = (width as f64 * scale).round() as u32;
+        let height = (height as f64 * scale).round() as u32;
+        Rect::new(
+            (window.0 as i32 - width as i32) / 2,
+            (window.1 as i32 - height as i32) / 2,
+            width,
+            height,
+        )
+    }
+
+    /**
+     * The destination to copy the unrotated picture to, such that rotating it around
+     * its center covers `picture`.
+     */
+    pub fn copy_target(&self, window: (u32, u32), pixels: (u32, u32)) -> Rect {
+        let picture = self.picture(window, pixels);
+        let (width, height) = self.oriented((picture.width(), picture.height()));
+        Rect::from_center(picture.center(), width, height)
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport::new()
+    }
+}
+
+#[cfg(test)]
+#[path = "./viewports_test.rs"]
+mod viewports_test;
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use sdl2::rect::Rect;
+
+    use crate::viewports::{Scaling, Viewport};
+
+    const PIXELS: (u32, u32) = (64, 32);
+
+    #[test]
+    fn test_integer_scaling_letterboxes() {
+        let viewport = Viewport::new();
+        assert_eq!(viewport.scale((1000, 1000), PIXELS), 15.0);
+        assert_eq!(
+            viewport.picture((1000, 1000), PIXELS),
+            Rect::new(20, 260, 960, 480)
+        );
+    }
+
+    #[test]
+    fn test_fit_scaling_fills_one_side() {
+        let mut viewport = Viewport::new();
+        viewport.scaling = Scaling::Fit;
+        assert_eq!(
+            viewport.picture((1000, 1000), PIXELS),
+            Rect::new(0, 250, 1000, 500)
+        );
+    }
+
+    #[test]
+    fn test_integer_scaling_is_at_least_one() {
+        let viewport = Viewport::new();
+        assert_eq!(viewport.scale((10, 10), PIXELS), 1.0);
+    }
+
+    #[test]
+    fn test_rotated_picture_is_portrait() {
+        let mut viewport = Viewport::new();
+        viewport.rotate();
+        assert_eq!(viewport.rotation, 90);
+        assert_eq!(
+            viewport.picture((640, 1280), PIXELS),
+            Rect::new(0, 0, 640, 1280)
+        );
+        // copied in landscape, then rotated around the center
+        assert_eq!(
+            viewport.copy_target((640, 1280), PIXELS),
+            Rect::new(-320, 320, 1280, 640)
+        );
+    }
+
+    #[test]
+    fn test_rotate_wraps_around() {
+        let mut viewport = Viewport::new();
+        (0..4).for_each(|_| viewport.rotate());
+        assert_eq!(viewport.rotation, 0);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Viewport::parse_rotation("270"), Ok(270));
+        assert!(Viewport::parse_rotation("45").is_err());
+        assert_eq!(Viewport::parse_flip("hv"), Ok((true, true)));
+        assert_eq!(Viewport::parse_scaling("fit"), Ok(Scaling::Fit));
+        assert!(Viewport::parse_scaling("stretch").is_err());
+    }
+}
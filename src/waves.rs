@@ -1,54 +1,311 @@
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 use sdl2::{
-    audio::{AudioCallback, AudioDevice, AudioSpecDesired},
+    audio::{AudioQueue, AudioSpecDesired},
     AudioSubsystem,
 };
 
-struct SquareWave {
-    pub phase_inc: f32,
-    pub phase: f32,
+use crate::beepers::Beeper;
+
+pub const DEFAULT_FREQUENCY: f32 = 440.0;
+pub const DEFAULT_VOLUME: f32 = 0.25;
+// how long the tone takes to fade in and out, short enough to sound like a beep but
+// long enough not to click
+const ENVELOPE_SECONDS: f32 = 0.005;
+// how many frames of sound to keep queued ahead of the audio device, enough to ride out
+// a late frame without the sound stuttering
+const QUEUED_FRAMES: u32 = 2;
+// more than this are dropped, e.g. while fast-forwarding
+pub(crate) const MAX_QUEUED_FRAMES: u32 = 6;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Waveform {
+    Square,
+    // a square wave that is high for this fraction of each period
+    Pulse(f32),
+    Triangle,
+    Sine,
+    // a new random level every period, for a pitched hiss
+    Noise,
+}
+
+impl Waveform {
+    /**
+     * Parses `square`, `pulse` with an optional duty cycle like `pulse:0.25`, `triangle`,
+     * `sine` or `noise`.
+     */
+    pub fn parse(text: &str) -> Result<Waveform, String> {
+        let (name, duty) = match text.split_once(':') {
+            Some((name, duty)) => (name, Some(duty)),
+            None => (text, None),
+        };
+        match (name, duty) {
+            ("square", None) => Ok(Waveform::Square),
+            ("pulse", None) => Ok(Waveform::Pulse(0.25)),
+            ("pulse", Some(duty)) => duty
+                .parse()
+                .ok()
+                .filter(|duty| *duty > 0.0 && *duty < 1.0)
+                .map(Waveform::Pulse)
+                .ok_or(format!(
+                    "invalid duty cycle {:?}, expected a fraction between 0 and 1",
+                    duty
+                )),
+            ("triangle", None) => Ok(Waveform::Triangle),
+            ("sine", None) => Ok(Waveform::Sine),
+            ("noise", None) => Ok(Waveform::Noise),
+            _ => Err(format!(
+                "unknown waveform {:?}, expected square, pulse[:duty], triangle, sine or noise",
+                text
+            )),
+        }
+    }
+}
+
+/**
+ * What the beep sounds like.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Tone {
+    // in Hz
+    pub frequency: f32,
+    // from 0 to 1
     pub volume: f32,
+    pub waveform: Waveform,
+}
+
+impl Tone {
+    pub fn new() -> Tone {
+        Tone {
+            frequency: DEFAULT_FREQUENCY,
+            volume: DEFAULT_VOLUME,
+            waveform: Waveform::Square,
+        }
+    }
+
+    /**
+     * The tone if it can be heard and played at 44.1 kHz.
+     */
+    pub fn validate(self) -> Result<Tone, String> {
+        if !(20.0..=20_000.0).contains(&self.frequency) {
+            return Err(format!(
+                "invalid frequency {}, expected 20 to 20000 Hz",
+                self.frequency
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(format!("invalid volume {}, expected 0 to 1", self.volume));
+        }
+        Ok(self)
+    }
+}
+
+impl Default for Tone {
+    fn default() -> Self {
+        Tone::new()
+    }
+}
+
+/**
+ * Generates the samples of the beep, one 60 Hz frame of emulated time at a time.
+ * Starting, stopping and muting ramp the volume rather than cutting the wave off, which
+ * would click.
+ */
+pub struct ToneGenerator {
+    tone: Tone,
+    sample_rate: u64,
+    // frames generated so far
+    frames: u64,
+    // how far through a period the wave is, from 0 to 1
+    phase: f32,
+    phase_inc: f32,
+    // the envelope, from 0 for silent to 1 for full volume
+    level: f32,
+    level_step: f32,
+    sounding: bool,
+    muted: bool,
+    noise: SmallRng,
+    noise_value: f32,
+}
+
+impl ToneGenerator {
+    pub fn new(tone: Tone, sample_rate: u32) -> ToneGenerator {
+        ToneGenerator {
+            tone,
+            sample_rate: sample_rate as u64,
+            frames: 0,
+            phase: 0.0,
+            phase_inc: tone.frequency / sample_rate as f32,
+            level: 0.0,
+            level_step: 1.0 / (ENVELOPE_SECONDS * sample_rate as f32).max(1.0),
+            sounding: false,
+            muted: false,
+            noise: SmallRng::seed_from_u64(0),
+            noise_value: 0.0,
+        }
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    fn wave(&mut self) -> f32 {
+        let phase = self.phase;
+        match self.tone.waveform {
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Pulse(duty) => {
+                if phase < duty {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+            Waveform::Sine => (phase * std::f32::consts::TAU).sin(),
+            Waveform::Noise => {
+                if phase < self.phase_inc {
+                    self.noise_value = self.noise.gen_range(-1.0..=1.0);
+                }
+                self.noise_value
+            }
+        }
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let target = if self.sounding && !self.muted {
+            1.0
+        } else {
+            0.0
+        };
+        if self.level < target {
+            self.level = (self.level + self.level_step).min(target);
+        } else if self.level > target {
+            self.level = (self.level - self.level_step).max(target);
+        }
+        let sample = if self.level > 0.0 {
+            self.wave() * self.tone.volume * self.level
+        } else {
+            0.0
+        };
+        self.phase = (self.phase + self.phase_inc) % 1.0;
+        sample
+    }
+
+    /**
+     * Appends the samples of the next frame, with the beep on or off for all of it.
+     * Frames are as long as at exactly 60 Hz on average, so a beep of N frames lasts
+     * exactly N / 60 seconds however fast the frames were emulated.
+     */
+    pub fn render_frame(&mut self, sounding: bool, out: &mut Vec<f32>) {
+        self.sounding = sounding;
+        let start = self.frames * self.sample_rate / 60;
+        self.frames += 1;
+        let end = self.frames * self.sample_rate / 60;
+        for _ in start..end {
+            let sample = self.next_sample();
+            out.push(sample);
+        }
+    }
+
+    pub fn samples_per_frame(&self) -> u32 {
+        (self.sample_rate / 60) as u32
+    }
 }
 
-impl AudioCallback for SquareWave {
-    type Channel = f32;
+/**
+ * What each frame adds to the audio device's queue, keeping a couple of frames queued
+ * ahead without letting the queue grow when frames are emulated faster than played.
+ */
+pub(crate) struct FrameQueue {
+    generator: ToneGenerator,
+    samples: Vec<f32>,
+}
 
-    fn callback(&mut self, out: &mut [f32]) {
-        // Generate a square wave
-        for x in out.iter_mut() {
-            *x = if self.phase <= 0.5 {
-                self.volume
-            } else {
-                -self.volume
-            };
-            self.phase = (self.phase + self.phase_inc) % 1.0;
+impl FrameQueue {
+    pub(crate) fn new(generator: ToneGenerator) -> FrameQueue {
+        FrameQueue {
+            generator,
+            samples: vec![],
         }
     }
+
+    pub(crate) fn set_muted(&mut self, muted: bool) {
+        self.generator.set_muted(muted);
+    }
+
+    /**
+     * The samples to queue for the next frame when `queued` samples are still waiting to
+     * be played. With the queue full only silent frames are dropped, as dropping part of
+     * a beep would cut its envelope off and click.
+     */
+    pub(crate) fn next_frame(&mut self, sounding: bool, queued: u32) -> &[f32] {
+        let frame = self.generator.samples_per_frame();
+        self.samples.clear();
+        if queued == 0 {
+            // ran dry, e.g. after a pause, so build up the queue again
+            self.samples.resize((frame * QUEUED_FRAMES) as usize, 0.0);
+        }
+        self.generator.render_frame(sounding, &mut self.samples);
+        if queued >= frame * MAX_QUEUED_FRAMES && self.samples.iter().all(|s| *s == 0.0) {
+            self.samples.clear();
+        }
+        &self.samples
+    }
 }
 
+/**
+ * Plays the beep on the default audio device. Every emulated frame queues its samples,
+ * beep or silence, so the sound follows emulated time rather than when the CPU thread
+ * got around to starting and stopping it.
+ */
 pub struct Audio {
-    device: AudioDevice<SquareWave>,
+    queue: AudioQueue<f32>,
+    frames: FrameQueue,
 }
+
 impl Audio {
-    pub fn new(system: AudioSubsystem) -> Result<Audio, String> {
+    pub fn new(system: AudioSubsystem, tone: Tone) -> Result<Audio, String> {
         let desired_audio_spec = AudioSpecDesired {
             freq: Some(44_100),
             channels: Some(1), // mono
             samples: None,
         };
-        let audio_device = system.open_playback(None, &desired_audio_spec, |spec| SquareWave {
-            phase_inc: 440.0 / spec.freq as f32,
-            phase: 0.0,
-            volume: 0.25,
-        })?;
+        let queue = system.open_queue(None, &desired_audio_spec)?;
+        queue.resume();
+        let generator = ToneGenerator::new(tone, queue.spec().freq as u32);
 
         Ok(Audio {
-            device: audio_device,
+            queue,
+            frames: FrameQueue::new(generator),
         })
     }
-    pub fn sound(&self) {
-        self.device.resume();
+
+    // in samples
+    fn queued(&self) -> u32 {
+        self.queue.size() / std::mem::size_of::<f32>() as u32
     }
-    pub fn pause(&self) {
-        self.device.pause();
+}
+
+impl Beeper for Audio {
+    fn play_frame(&mut self, sounding: bool) {
+        let queued = self.queued();
+        let samples = self.frames.next_frame(sounding, queued);
+        if !samples.is_empty() {
+            let _ = self.queue.queue_audio(samples);
+        }
+    }
+
+    fn set_muted(&mut self, muted: bool) {
+        self.frames.set_muted(muted);
     }
 }
+
+#[cfg(test)]
+#[path = "./waves_test.rs"]
+mod waves_test;
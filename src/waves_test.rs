@@ -0,0 +1,142 @@
+#[cfg(test)]
+mod tests {
+    use crate::waves::{FrameQueue, Tone, ToneGenerator, Waveform, MAX_QUEUED_FRAMES};
+
+    // 100 samples a frame, the envelope takes 30 samples and a period 8
+    const SAMPLE_RATE: u32 = 6000;
+
+    fn generator(waveform: Waveform) -> ToneGenerator {
+        let tone = Tone {
+            frequency: 750.0,
+            volume: 1.0,
+            waveform,
+        };
+        ToneGenerator::new(tone, SAMPLE_RATE)
+    }
+
+    fn frame(generator: &mut ToneGenerator, sounding: bool) -> Vec<f32> {
+        let mut out = vec![];
+        generator.render_frame(sounding, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_parse_waveform() {
+        assert_eq!(Waveform::parse("square"), Ok(Waveform::Square));
+        assert_eq!(Waveform::parse("pulse"), Ok(Waveform::Pulse(0.25)));
+        assert_eq!(Waveform::parse("pulse:0.125"), Ok(Waveform::Pulse(0.125)));
+        assert_eq!(Waveform::parse("sine"), Ok(Waveform::Sine));
+        assert!(Waveform::parse("pulse:1.5").is_err());
+        assert!(Waveform::parse("sawtooth").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Tone::new().validate().is_ok());
+        let tone = Tone {
+            volume: 2.0,
+            ..Tone::new()
+        };
+        assert!(tone.validate().is_err());
+        let tone = Tone {
+            frequency: 0.0,
+            ..Tone::new()
+        };
+        assert!(tone.validate().is_err());
+    }
+
+    #[test]
+    fn test_frames_last_a_sixtieth_of_a_second() {
+        let tone = Tone::new();
+        let mut generator = ToneGenerator::new(tone, 44_100);
+        assert_eq!(frame(&mut generator, true).len(), 735);
+
+        // 8000 samples a second don't divide into 60 frames evenly
+        let mut generator = ToneGenerator::new(tone, 8000);
+        let lengths: Vec<usize> = (0..60).map(|_| frame(&mut generator, true).len()).collect();
+        assert!(lengths.iter().all(|len| *len == 133 || *len == 134));
+        assert_eq!(lengths.iter().sum::<usize>(), 8000);
+    }
+
+    #[test]
+    fn test_silent_until_sounding() {
+        let mut generator = generator(Waveform::Square);
+        assert!(frame(&mut generator, false).iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn test_envelope_fades_in_and_out() {
+        let mut generator = generator(Waveform::Square);
+        let beep = frame(&mut generator, true);
+        assert!(beep[0].abs() < 0.05);
+        assert!(beep[..30].windows(2).all(|w| w[1].abs() >= w[0].abs()));
+        assert_eq!(beep[32..40], [1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0]);
+
+        let release = frame(&mut generator, false);
+        assert!(release[0].abs() > 0.9);
+        assert!(release.windows(2).all(|w| w[1].abs() <= w[0].abs()));
+        assert!(release[40..].iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn test_full_queue_keeps_beeps_whole() {
+        let tone = Tone {
+            frequency: 50.0,
+            volume: 1.0,
+            waveform: Waveform::Sine,
+        };
+        let sounding = |frame: u32| (4..4 + 3 * MAX_QUEUED_FRAMES).contains(&frame);
+        let frames = 6 * MAX_QUEUED_FRAMES;
+        let mut expected = vec![];
+        let mut generator = ToneGenerator::new(tone, SAMPLE_RATE);
+        for frame in 0..frames {
+            generator.render_frame(sounding(frame), &mut expected);
+        }
+
+        // emulated twice as fast as the device plays, so the queue fills up
+        let mut queue = FrameQueue::new(ToneGenerator::new(tone, SAMPLE_RATE));
+        let mut played = vec![];
+        let mut queued = 0u32;
+        for frame in 0..frames {
+            let samples = queue.next_frame(sounding(frame), queued);
+            played.extend_from_slice(samples);
+            queued = (queued + samples.len() as u32).saturating_sub(50);
+        }
+
+        assert!(played.len() < expected.len());
+        let audible = |samples: &[f32]| samples.iter().filter(|s| **s != 0.0).count();
+        assert_eq!(audible(&played), audible(&expected));
+        // a 50 Hz sine at 6 kHz moves at most 0.053 a sample and the envelope 0.034
+        for pair in played.windows(2) {
+            assert!((pair[1] - pair[0]).abs() < 0.1, "{:?}", pair);
+        }
+    }
+
+    #[test]
+    fn test_muted() {
+        let mut generator = generator(Waveform::Square);
+        generator.set_muted(true);
+        assert!(frame(&mut generator, true).iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn test_waveforms() {
+        let steady = |waveform| {
+            let mut generator = generator(waveform);
+            frame(&mut generator, true)[40..56].to_vec()
+        };
+        assert_eq!(
+            steady(Waveform::Pulse(0.25))[..8],
+            [1.0, 1.0, -1.0, -1.0, -1.0, -1.0, -1.0, -1.0]
+        );
+        assert_eq!(
+            steady(Waveform::Triangle)[..8],
+            [1.0, 0.5, 0.0, -0.5, -1.0, -0.5, 0.0, 0.5]
+        );
+        // one random level per period
+        let noise = steady(Waveform::Noise);
+        assert!(noise.iter().all(|s| (-1.0..=1.0).contains(s)));
+        assert!(noise[..8].iter().all(|s| *s == noise[0]));
+        assert_ne!(noise[0], noise[8]);
+    }
+}